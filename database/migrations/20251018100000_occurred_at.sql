@@ -0,0 +1,3 @@
+alter table amount_record add column occurred_at DATETIME not null default '1970-01-01 00:00:00';
+update amount_record set occurred_at = created_at;
+create index if not exists idx_amount_record_occurred_at on amount_record (occurred_at);
@@ -0,0 +1,11 @@
+create table if not exists account (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT not null unique,
+    archived BOOLEAN not null default false,
+    created_at DATETIME not null default CURRENT_TIMESTAMP,
+    updated_at DATETIME not null default CURRENT_TIMESTAMP
+);
+insert into account (name) values ('default');
+alter table amount_record add column account_id INTEGER references account (id);
+update amount_record set account_id = (select min(id) from account);
+create index if not exists idx_amount_record_account_id on amount_record (account_id);
@@ -0,0 +1,6 @@
+create table if not exists transfer (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at DATETIME not null default CURRENT_TIMESTAMP
+);
+alter table amount_record add column transfer_id INTEGER references transfer (id);
+create index if not exists idx_amount_record_transfer_id on amount_record (transfer_id);
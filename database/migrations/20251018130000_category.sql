@@ -0,0 +1,18 @@
+create table if not exists category (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT not null,
+    parent_id INTEGER references category (id),
+    created_at DATETIME not null default CURRENT_TIMESTAMP,
+    updated_at DATETIME not null default CURRENT_TIMESTAMP
+);
+create unique index if not exists idx_category_parent_name on category (ifnull(parent_id, 0), name);
+create view if not exists category_path as
+with recursive tree (id, path) as (
+    select id, name from category where parent_id is null
+    union all
+    select category.id, tree.path || ':' || category.name
+    from category join tree on category.parent_id = tree.id
+)
+select id, path from tree;
+alter table amount_record add column category_id INTEGER references category (id);
+create index if not exists idx_amount_record_category_id on amount_record (category_id);
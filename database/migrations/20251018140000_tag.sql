@@ -0,0 +1,11 @@
+create table if not exists tag (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT not null unique,
+    created_at DATETIME not null default CURRENT_TIMESTAMP
+);
+create table if not exists record_tag (
+    record_id INTEGER not null references amount_record (id) on delete cascade,
+    tag_id INTEGER not null references tag (id) on delete cascade,
+    primary key (record_id, tag_id)
+);
+create index if not exists idx_record_tag_tag_id on record_tag (tag_id);
@@ -0,0 +1,9 @@
+create table if not exists budget (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    category_id INTEGER references category (id) on delete cascade,
+    month TEXT,
+    amount INTEGER not null,
+    created_at DATETIME not null default CURRENT_TIMESTAMP,
+    updated_at DATETIME not null default CURRENT_TIMESTAMP
+);
+create unique index if not exists idx_budget_category_month on budget (ifnull(category_id, 0), ifnull(month, ''));
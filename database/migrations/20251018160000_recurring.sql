@@ -0,0 +1,16 @@
+create table if not exists recurring (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    amount INTEGER not null,
+    in_or_out BOOLEAN not null,
+    append_msg TEXT not null,
+    account_id INTEGER references account (id),
+    category_id INTEGER references category (id) on delete set null,
+    tags TEXT not null default '',
+    unit TEXT not null,
+    step INTEGER not null default 1,
+    start_date DATE not null,
+    end_date DATE,
+    materialized INTEGER not null default 0,
+    created_at DATETIME not null default CURRENT_TIMESTAMP,
+    updated_at DATETIME not null default CURRENT_TIMESTAMP
+);
@@ -0,0 +1,3 @@
+alter table amount_record add column source TEXT;
+alter table amount_record add column external_id TEXT;
+create index if not exists idx_amount_record_external_id on amount_record (source, external_id);
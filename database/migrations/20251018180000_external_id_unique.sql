@@ -0,0 +1,2 @@
+drop index if exists idx_amount_record_external_id;
+create unique index if not exists idx_amount_record_external_id on amount_record (source, external_id) where external_id is not null;
@@ -0,0 +1,15 @@
+create table if not exists operation (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    command TEXT not null,
+    undone BOOLEAN not null default 0,
+    created_at DATETIME not null default CURRENT_TIMESTAMP
+);
+create table if not exists operation_row (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    operation_id INTEGER not null references operation (id) on delete cascade,
+    record_id INTEGER not null,
+    before TEXT,
+    after TEXT
+);
+create index if not exists idx_operation_row_operation_id on operation_row (operation_id);
+create index if not exists idx_operation_row_record_id on operation_row (record_id);
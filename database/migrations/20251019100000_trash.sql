@@ -0,0 +1,2 @@
+alter table amount_record add column deleted_at DATETIME;
+create index if not exists idx_amount_record_deleted_at on amount_record (deleted_at);
@@ -0,0 +1,2 @@
+alter table operation add column author TEXT;
+alter table operation add column discarded BOOLEAN not null default 0;
@@ -0,0 +1,10 @@
+create table if not exists ledger (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    command TEXT not null,
+    record_id INTEGER,
+    before TEXT,
+    after TEXT,
+    created_at DATETIME not null,
+    prev_hash TEXT not null,
+    hash TEXT not null
+);
@@ -0,0 +1,21 @@
+create virtual table if not exists record_search using fts5 (
+    append_msg,
+    content = 'amount_record',
+    content_rowid = 'id',
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+create trigger if not exists record_search_insert after insert on amount_record begin
+    insert into record_search (rowid, append_msg) values (new.id, new.append_msg);
+end;
+
+create trigger if not exists record_search_delete after delete on amount_record begin
+    insert into record_search (record_search, rowid, append_msg) values ('delete', old.id, old.append_msg);
+end;
+
+create trigger if not exists record_search_update after update of append_msg on amount_record begin
+    insert into record_search (record_search, rowid, append_msg) values ('delete', old.id, old.append_msg);
+    insert into record_search (rowid, append_msg) values (new.id, new.append_msg);
+end;
+
+insert into record_search (record_search) values ('rebuild');
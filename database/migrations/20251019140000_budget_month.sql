@@ -0,0 +1,8 @@
+update budget set month = substr(month, 1, 5) || '0' || substr(month, 6)
+where month glob '[0-9][0-9][0-9][0-9]-[1-9]'
+    and not exists (
+        select 1 from budget as other
+        where ifnull(other.category_id, 0) = ifnull(budget.category_id, 0)
+            and other.month = substr(budget.month, 1, 5) || '0' || substr(budget.month, 6)
+    );
+delete from budget where month glob '[0-9][0-9][0-9][0-9]-[1-9]';
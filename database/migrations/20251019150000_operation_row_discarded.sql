@@ -0,0 +1,2 @@
+alter table operation_row add column discarded BOOLEAN not null default 0;
+update operation_row set discarded = 1 where record_id not in (select id from amount_record);
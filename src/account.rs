@@ -0,0 +1,230 @@
+use crate::cli_customs::{
+    AccountArgs, AccountCommands, AccountListArgs, AccountNameArgs, AccountRenameArgs,
+};
+use crate::money::Money;
+use crate::output::{print_notice, print_rows, print_table};
+use chrono::Utc;
+use colored::*;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use std::error::Error;
+use std::path::PathBuf;
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Panel,
+        object::Rows,
+        style::{Border, BorderColor, LineText, Style},
+        themes::Colorization,
+    },
+};
+
+/// an account with its balance, e.g. cash, bank card, alipay.
+#[derive(FromRow, Clone, Debug, Tabled, Serialize)]
+pub struct AccountBalance {
+    pub id: i64,
+    pub name: String,
+    pub archived: bool,
+    pub records: i64,
+    pub balance: Money,
+}
+
+/// dispatch the `account` subcommands.
+pub async fn account_command(
+    args: &AccountArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    match &args.command {
+        AccountCommands::Add(arg) => add_account(arg, &pool).await,
+        AccountCommands::List(arg) => list_accounts(arg, &pool).await,
+        AccountCommands::Rename(arg) => rename_account(arg, &pool).await,
+        AccountCommands::Archive(arg) => archive_account(arg, &pool).await,
+    }
+}
+
+async fn add_account(args: &AccountNameArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let name = args.name.trim();
+    if name.is_empty() {
+        return Err("the account name can not be empty".into());
+    }
+    if find_account(pool, name).await?.is_some() {
+        return Err(format!("account `{}` already exists", name).into());
+    }
+
+    sqlx::query("INSERT INTO account (name, created_at, updated_at) VALUES (?, ?, ?)")
+        .bind(name)
+        .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+        .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+        .execute(pool)
+        .await?;
+
+    print_notice(format!(
+        "{}{}",
+        "Added account ".yellow().bold(),
+        name.purple().bold()
+    ));
+    Ok(())
+}
+
+async fn list_accounts(args: &AccountListArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let accounts = account_balances(pool, args.all).await?;
+    format_output_account_balances(&accounts)
+}
+
+async fn rename_account(args: &AccountRenameArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let new_name = args.new_name.trim();
+    if new_name.is_empty() {
+        return Err("the account name can not be empty".into());
+    }
+    let id = find_account(pool, &args.name)
+        .await?
+        .ok_or(format!("account `{}` does not exist", args.name))?
+        .0;
+    if find_account(pool, new_name).await?.is_some() {
+        return Err(format!("account `{}` already exists", new_name).into());
+    }
+
+    sqlx::query("UPDATE account SET name = ?, updated_at = ? WHERE id = ?")
+        .bind(new_name)
+        .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    print_notice(format!(
+        "{}{}{}{}",
+        "Renamed account ".yellow().bold(),
+        args.name.purple().bold(),
+        " to ".yellow().bold(),
+        new_name.purple().bold()
+    ));
+    Ok(())
+}
+
+async fn archive_account(args: &AccountNameArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let (id, archived) = find_account(pool, &args.name)
+        .await?
+        .ok_or(format!("account `{}` does not exist", args.name))?;
+    if archived {
+        return Err(format!("account `{}` is already archived", args.name).into());
+    }
+
+    sqlx::query("UPDATE account SET archived = true, updated_at = ? WHERE id = ?")
+        .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    print_notice(format!(
+        "{}{}",
+        "Archived account ".yellow().bold(),
+        args.name.purple().bold()
+    ));
+    Ok(())
+}
+
+/// find an account by name, returns its id and whether it is archived.
+pub async fn find_account(
+    pool: &SqlitePool,
+    name: &str,
+) -> Result<Option<(i64, bool)>, Box<dyn Error>> {
+    Ok(
+        sqlx::query_as::<_, (i64, bool)>("SELECT id, archived FROM account WHERE name = ?")
+            .bind(name.trim())
+            .fetch_optional(pool)
+            .await?,
+    )
+}
+
+/// resolve the account a new record goes to.
+/// `None` means the first account which is not archived.
+pub async fn resolve_account(pool: &SqlitePool, name: Option<&str>) -> Result<i64, Box<dyn Error>> {
+    match name {
+        Some(name) => {
+            let (id, archived) = find_account(pool, name)
+                .await?
+                .ok_or(format!("account `{}` does not exist", name))?;
+            if archived {
+                return Err(format!("account `{}` is archived", name).into());
+            }
+            Ok(id)
+        }
+        None => sqlx::query_as::<_, (i64,)>(
+            "SELECT id FROM account WHERE archived = false ORDER BY id LIMIT 1",
+        )
+        .fetch_optional(pool)
+        .await?
+        .map(|x| x.0)
+        .ok_or("no account available, add one with `rustance account add <name>`".into()),
+    }
+}
+
+/// the balance of every account.
+pub async fn account_balances(
+    pool: &SqlitePool,
+    include_archived: bool,
+) -> Result<Vec<AccountBalance>, Box<dyn Error>> {
+    let query = r#"
+        SELECT
+            account.id, account.name, account.archived,
+            COUNT(amount_record.id) AS records,
+            COALESCE(SUM(CASE WHEN amount_record.in_or_out THEN amount_record.amount ELSE -amount_record.amount END), 0) AS balance
+        FROM
+            account
+            LEFT JOIN amount_record
+                ON amount_record.account_id = account.id AND amount_record.deleted_at IS NULL
+        WHERE
+            account.archived = false OR ?
+        GROUP BY
+            account.id
+        ORDER BY
+            account.id
+    "#;
+
+    Ok(sqlx::query_as(query)
+        .bind(include_archived)
+        .fetch_all(pool)
+        .await?)
+}
+
+/// use tabled to format the balances of the accounts, with the grand total.
+pub fn format_output_account_balances(accounts: &[AccountBalance]) -> Result<(), Box<dyn Error>> {
+    if print_rows(accounts)? {
+        return Ok(());
+    }
+    let mut rows = accounts.to_vec();
+    rows.push(AccountBalance {
+        id: 0,
+        name: "Grand Total".to_string(),
+        archived: false,
+        records: accounts.iter().map(|x| x.records).sum(),
+        balance: Money::total(accounts.iter().map(|x| x.balance))?,
+    });
+
+    let table = Table::new(rows)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_BRIGHT_RED,
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_BLUE,
+        ]))
+        .with(Panel::header("Account Balances\n"))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .with(Highlight::new(Rows::last()).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::last()).border(Border::new().top('━')))
+        .with(LineText::new("Statistics", Rows::last()).color(Color::BG_YELLOW | Color::FG_BLACK))
+        .to_string();
+
+    print_table(table);
+
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use crate::import::{ImportRecord, parse_decimal, parse_import_date};
+use crate::tag::parse_tags;
+use std::error::Error;
+
+/// the headers of each column in Alipay and WeChat Pay bills,
+/// a header matches if it starts with one of them.
+const TIME_COLUMN: &[&str] = &["交易时间", "交易创建时间", "付款时间"];
+const DIRECTION_COLUMN: &[&str] = &["收/支"];
+const AMOUNT_COLUMN: &[&str] = &["金额"];
+const STATUS_COLUMN: &[&str] = &["交易状态", "当前状态"];
+const TRADE_NO_COLUMN: &[&str] = &["交易订单号", "交易单号", "交易号"];
+const TYPE_COLUMN: &[&str] = &["交易类型", "交易分类", "类型"];
+const COUNTERPARTY_COLUMN: &[&str] = &["交易对方"];
+const GOODS_COLUMN: &[&str] = &["商品说明", "商品名称", "商品"];
+const NOTE_COLUMN: &[&str] = &["备注"];
+const REFUND_COLUMN: &[&str] = &["成功退款"];
+
+/// the statuses of trades which did not happen.
+const SKIPPED_STATUS: &[&str] = &["关闭", "失败", "撤销", "取消", "等待", "未支付"];
+
+/// the types of 不计收支 trades which move money into the account,
+/// the others move money out of it, e.g. 提现 or 还款.
+const TRANSFER_IN_TYPE: &[&str] = &["充值"];
+
+/// decode a bill, Alipay exports GBK while WeChat Pay exports UTF-8.
+pub fn decode_bill(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(content) => content.to_string(),
+        Err(_) => encoding_rs::GB18030.decode(bytes).0.into_owned(),
+    }
+}
+
+/// find the first column whose header starts with one of `names`.
+fn find_bill_column(headers: &[String], names: &[&str]) -> Option<usize> {
+    names
+        .iter()
+        .find_map(|name| headers.iter().position(|x| x.starts_with(name)))
+}
+
+/// read the records of an Alipay or WeChat Pay bill.
+/// the preamble before the header and the footer are left out.
+/// returns the records and how many closed, failed or refunded trades are skipped.
+pub fn read_bill(
+    content: &str,
+    tags: &[String],
+) -> Result<(Vec<ImportRecord>, usize), Box<dyn Error>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .has_headers(false)
+        .from_reader(content.as_bytes());
+    let mut rows = reader.records();
+
+    let headers: Vec<String> = loop {
+        match rows.next() {
+            Some(row) => {
+                let headers: Vec<String> = row?.iter().map(|x| x.trim().to_string()).collect();
+                if find_bill_column(&headers, TIME_COLUMN).is_some()
+                    && find_bill_column(&headers, DIRECTION_COLUMN).is_some()
+                {
+                    break headers;
+                }
+            }
+            None => {
+                return Err(
+                    "not an Alipay or WeChat Pay bill, no header with 交易时间 and 收/支".into(),
+                );
+            }
+        }
+    };
+    let required = |names: &[&str]| -> Result<usize, String> {
+        find_bill_column(&headers, names)
+            .ok_or(format!("the bill has no column {}", names.join("/")))
+    };
+    let time = required(TIME_COLUMN)?;
+    let direction = required(DIRECTION_COLUMN)?;
+    let amount = required(AMOUNT_COLUMN)?;
+    let status = required(STATUS_COLUMN)?;
+    let trade_no = required(TRADE_NO_COLUMN)?;
+    let trade_type = find_bill_column(&headers, TYPE_COLUMN);
+    let msg: Vec<usize> = [COUNTERPARTY_COLUMN, GOODS_COLUMN, NOTE_COLUMN]
+        .iter()
+        .filter_map(|names| find_bill_column(&headers, names))
+        .collect();
+    let refund = find_bill_column(&headers, REFUND_COLUMN);
+    let last_required = [time, direction, amount, status, trade_no]
+        .into_iter()
+        .max()
+        .unwrap();
+
+    let mut records = Vec::new();
+    let mut skipped = 0;
+    for row in rows {
+        let row = row?;
+        // the footer lines like `------` or `共 12 笔记录`.
+        if row.len() <= last_required || row[time].trim().is_empty() {
+            continue;
+        }
+        let line = row.position().map_or(0, |x| x.line() as usize);
+        let cell = |i: usize| row.get(i).unwrap_or("").trim();
+        let at_line = |e: String| format!("line {}: {}", line, e);
+
+        let status = cell(status);
+        if SKIPPED_STATUS.iter().any(|x| status.contains(x)) {
+            skipped += 1;
+            continue;
+        }
+        let mut money = parse_decimal(cell(amount), '.')
+            .map_err(at_line)?
+            .unwrap_or_default()
+            .abs();
+        if let Some(refund) = refund {
+            money = money
+                - parse_decimal(cell(refund), '.')
+                    .map_err(at_line)?
+                    .unwrap_or_default()
+                    .abs();
+        }
+        if !money.is_positive() {
+            skipped += 1;
+            continue;
+        }
+
+        let trade_type = trade_type.map(cell).unwrap_or("");
+        let (in_or_out, transfer) = match cell(direction) {
+            "收入" => (true, false),
+            "支出" => (false, false),
+            // money back from a refund.
+            _ if status.contains("退款") => (true, false),
+            _ => (
+                TRANSFER_IN_TYPE
+                    .iter()
+                    .any(|x| trade_type.contains(x) || msg.iter().any(|i| cell(*i).contains(x))),
+                true,
+            ),
+        };
+
+        let mut parts: Vec<&str> = Vec::new();
+        for part in msg.iter().map(|i| cell(*i)) {
+            if !part.is_empty() && part != "/" && !parts.contains(&part) {
+                parts.push(part);
+            }
+        }
+        let append_msg = parts.join(" ");
+        let external_id = Some(cell(trade_no).to_string()).filter(|x| !x.is_empty());
+
+        records.push(ImportRecord {
+            line,
+            amount: money,
+            in_or_out,
+            occurred_at: parse_import_date(cell(time), None).map_err(at_line)?,
+            tags: parse_tags(Some(&append_msg), tags).map_err(at_line)?,
+            append_msg,
+            transfer,
+            external_id,
+            ..Default::default()
+        });
+    }
+    Ok((records, skipped))
+}
@@ -0,0 +1,429 @@
+use crate::category::{path_with_ancestors, resolve_category};
+use crate::cli_customs::{
+    BudgetArgs, BudgetCommands, BudgetListArgs, BudgetSetArgs, BudgetTargetArgs, OutputFormat,
+};
+use crate::money::Money;
+use crate::output::{output_format, print_csv, print_json, print_notice, print_table};
+use crate::time_utils::{month_range, parse_month, to_local};
+use chrono::Utc;
+use colored::*;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use std::error::Error;
+use std::path::PathBuf;
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Panel,
+        object::Rows,
+        style::{Border, BorderColor, Style},
+        themes::Colorization,
+    },
+};
+
+/// the budget of a month with how much is spent.
+/// `category` is `None` for the overall budget.
+#[derive(FromRow, Clone, Debug)]
+pub struct BudgetStatus {
+    pub category: Option<String>,
+    pub budget: Money,
+    #[sqlx(skip)]
+    pub spent: Money,
+}
+
+impl BudgetStatus {
+    pub fn remaining(&self) -> Money {
+        self.budget - self.spent
+    }
+
+    /// the name shown in tables and warnings.
+    pub fn label(&self) -> String {
+        self.category.clone().unwrap_or("(overall)".to_string())
+    }
+}
+
+/// a budget as it is stored.
+#[derive(FromRow, Clone, Debug, Tabled, Serialize)]
+struct BudgetRow {
+    category: String,
+    month: String,
+    budget: Money,
+}
+
+/// a row of the budget status output.
+#[derive(Clone, Debug, Tabled, Serialize)]
+struct BudgetStatusRow {
+    category: String,
+    budget: Money,
+    spent: Money,
+    remaining: Money,
+}
+
+/// the budgets with the status of a month, for the json output.
+#[derive(Serialize, Debug)]
+struct BudgetReport {
+    month: String,
+    budgets: Vec<BudgetRow>,
+    status: Vec<BudgetStatusRow>,
+}
+
+/// dispatch the `budget` subcommands.
+pub async fn budget_command(
+    args: &BudgetArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    match &args.command {
+        BudgetCommands::Set(arg) => set_budget(arg, &pool).await,
+        BudgetCommands::List(arg) => list_budgets(arg, &pool).await,
+        BudgetCommands::Remove(arg) => remove_budget(arg, &pool).await,
+    }
+}
+
+/// resolve the category and validate the month of a budget.
+async fn budget_target(
+    args: &BudgetTargetArgs,
+    pool: &SqlitePool,
+) -> Result<(Option<i64>, Option<String>), Box<dyn Error>> {
+    let category_id = match args.category {
+        Some(ref category) => Some(resolve_category(pool, category).await?),
+        None => None,
+    };
+    let month = match args.month {
+        Some(ref month) => Some(budget_month(month)?),
+        None => None,
+    };
+    Ok((category_id, month))
+}
+
+/// the month of a budget as it is stored, e.g. `2025-03` for `2025-3`.
+fn budget_month(month: &str) -> Result<String, String> {
+    month_range(month)?;
+    Ok(parse_month(month)?.format("%Y-%m").to_string())
+}
+
+/// find the id of the budget of `category_id` in `month`.
+async fn find_budget(
+    pool: &SqlitePool,
+    category_id: Option<i64>,
+    month: Option<&str>,
+) -> Result<Option<i64>, Box<dyn Error>> {
+    Ok(sqlx::query_as::<_, (i64,)>(
+        "SELECT id FROM budget WHERE ifnull(category_id, 0) = ifnull(?, 0) AND ifnull(month, '') = ifnull(?, '')",
+    )
+    .bind(category_id)
+    .bind(month)
+    .fetch_optional(pool)
+    .await?
+    .map(|x| x.0))
+}
+
+async fn set_budget(args: &BudgetSetArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let (category_id, month) = budget_target(&args.target, pool).await?;
+
+    match find_budget(pool, category_id, month.as_deref()).await? {
+        Some(id) => {
+            sqlx::query("UPDATE budget SET amount = ?, updated_at = ? WHERE id = ?")
+                .bind(args.amount)
+                .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+                .bind(id)
+                .execute(pool)
+                .await?;
+        }
+        None => {
+            sqlx::query(
+                "INSERT INTO budget (category_id, month, amount, created_at, updated_at) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(category_id)
+            .bind(&month)
+            .bind(args.amount)
+            .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+            .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+            .execute(pool)
+            .await?;
+        }
+    }
+
+    print_notice(format!(
+        "{}{}{}{}: {}",
+        "Set budget of ".yellow().bold(),
+        args.target
+            .category
+            .as_deref()
+            .unwrap_or("(overall)")
+            .purple()
+            .bold(),
+        " for ".yellow().bold(),
+        month.as_deref().unwrap_or("every month").purple().bold(),
+        args.amount.to_string().purple().bold()
+    ));
+    Ok(())
+}
+
+async fn remove_budget(args: &BudgetTargetArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let (category_id, month) = budget_target(args, pool).await?;
+    let id = find_budget(pool, category_id, month.as_deref())
+        .await?
+        .ok_or("the budget does not exist")?;
+
+    sqlx::query("DELETE FROM budget WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    print_notice(format!(
+        "{}{}{}{}",
+        "Removed budget of ".yellow().bold(),
+        args.category
+            .as_deref()
+            .unwrap_or("(overall)")
+            .purple()
+            .bold(),
+        " for ".yellow().bold(),
+        month.as_deref().unwrap_or("every month").purple().bold()
+    ));
+    Ok(())
+}
+
+async fn list_budgets(args: &BudgetListArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let query = r#"
+        SELECT
+            ifnull(category_path.path, '(overall)') AS category,
+            ifnull(budget.month, 'every month') AS month,
+            budget.amount AS budget
+        FROM
+            budget
+            LEFT JOIN category_path ON category_path.id = budget.category_id
+        ORDER BY
+            budget.category_id IS NOT NULL, category_path.path, budget.month IS NOT NULL, budget.month
+    "#;
+    let budgets: Vec<BudgetRow> = sqlx::query_as(query).fetch_all(pool).await?;
+    let month = args
+        .month
+        .clone()
+        .unwrap_or(to_local(Utc::now()).format("%Y-%m").to_string());
+    let status = month_budget_status(pool, &month).await?;
+    let rows: Vec<BudgetStatusRow> = status
+        .iter()
+        .map(|x| BudgetStatusRow {
+            category: x.label(),
+            budget: x.budget,
+            spent: x.spent,
+            remaining: x.remaining(),
+        })
+        .collect();
+
+    match output_format() {
+        OutputFormat::Json => {
+            return print_json(&BudgetReport {
+                month,
+                budgets,
+                status: rows,
+            });
+        }
+        OutputFormat::Csv => return print_csv(&rows),
+        OutputFormat::Table | OutputFormat::Plain => {}
+    }
+    if budgets.is_empty() {
+        print_notice(
+            "No budget yet, set one with `rustance budget set <amount>`."
+                .yellow()
+                .bold(),
+        );
+        return Ok(());
+    }
+
+    let table = Table::new(budgets)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_MAGENTA,
+            Color::FG_BRIGHT_BLUE,
+        ]))
+        .with(Panel::header("Budgets\n"))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .to_string();
+    print_table(table);
+
+    let table = Table::new(rows)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_BRIGHT_BLUE,
+            Color::FG_BRIGHT_RED,
+            Color::FG_BRIGHT_GREEN,
+        ]))
+        .with(Panel::header(format!("Budgets Of the Month {}\n", month)))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .to_string();
+    print_table(table);
+
+    Ok(())
+}
+
+/// the budgets in effect for `month` with how much is spent.
+/// the spending is the outcome of the month, children categories included,
+/// transfers left out.
+pub async fn month_budget_status(
+    pool: &SqlitePool,
+    month: &str,
+) -> Result<Vec<BudgetStatus>, Box<dyn Error>> {
+    let (start, end) = month_range(month)?;
+    let month = budget_month(month)?;
+
+    let query = r#"
+        SELECT
+            category_path.path AS category,
+            budget.amount AS budget
+        FROM
+            budget
+            LEFT JOIN category_path ON category_path.id = budget.category_id
+        WHERE
+            budget.month = ?
+            OR (
+                budget.month IS NULL
+                AND NOT EXISTS (
+                    SELECT 1 FROM budget AS this_month
+                    WHERE this_month.month = ? AND ifnull(this_month.category_id, 0) = ifnull(budget.category_id, 0)
+                )
+            )
+        ORDER BY
+            budget.category_id IS NOT NULL, category_path.path
+    "#;
+    let mut status: Vec<BudgetStatus> = sqlx::query_as(query)
+        .bind(&month)
+        .bind(&month)
+        .fetch_all(pool)
+        .await?;
+
+    let query_spent = r#"
+        SELECT
+            COALESCE(SUM(amount_record.amount), 0)
+        FROM
+            amount_record
+            LEFT JOIN category_path ON category_path.id = amount_record.category_id
+        WHERE
+            amount_record.in_or_out = false
+            AND amount_record.transfer_id IS NULL
+            AND amount_record.deleted_at IS NULL
+            AND amount_record.occurred_at >= ?
+            AND amount_record.occurred_at < ?
+            AND (
+                ? IS NULL
+                OR category_path.path = ?
+                OR substr(category_path.path, 1, length(?) + 1) = ? || ':'
+            )
+    "#;
+    for x in status.iter_mut() {
+        let (spent,) = sqlx::query_as::<_, (Money,)>(query_spent)
+            .bind(start.format("%Y-%m-%d %H:%M:%S").to_string())
+            .bind(end.format("%Y-%m-%d %H:%M:%S").to_string())
+            .bind(&x.category)
+            .bind(&x.category)
+            .bind(&x.category)
+            .bind(&x.category)
+            .fetch_one(pool)
+            .await?;
+        x.spent = spent;
+    }
+
+    Ok(status)
+}
+
+/// print a warning for every budget an outcome of `category_id` in `month` is over.
+pub async fn warn_over_budget(
+    pool: &SqlitePool,
+    month: &str,
+    category_id: Option<i64>,
+) -> Result<(), Box<dyn Error>> {
+    let category = sqlx::query_as::<_, (String,)>("SELECT path FROM category_path WHERE id = ?")
+        .bind(category_id)
+        .fetch_optional(pool)
+        .await?;
+    let ancestors = category
+        .map(|x| path_with_ancestors(&x.0))
+        .unwrap_or_default();
+
+    for x in month_budget_status(pool, month).await? {
+        let applies = match x.category {
+            Some(ref budget_category) => ancestors.contains(budget_category),
+            None => true,
+        };
+        if applies && x.remaining().is_negative() {
+            print_notice(format!(
+                "{}{}{}{}{}{}",
+                "Warning: ".red().bold(),
+                x.label().purple().bold(),
+                " is over budget by ".red().bold(),
+                x.remaining().abs().to_string().purple().bold(),
+                format!(" ({} / {}) in ", x.spent, x.budget).red().bold(),
+                month.purple().bold()
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_database;
+
+    fn set(amount: &str, month: &str) -> BudgetArgs {
+        BudgetArgs {
+            command: BudgetCommands::Set(BudgetSetArgs {
+                amount: amount.parse().unwrap(),
+                target: BudgetTargetArgs {
+                    category: None,
+                    month: Some(month.to_string()),
+                },
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn budget_month_is_normalized() {
+        let database = test_database("budget-month").await;
+        budget_command(&set("300", "2025-3"), database.clone())
+            .await
+            .unwrap();
+        // the same budget, so it is updated instead of added twice.
+        budget_command(&set("400", "2025-03"), database.clone())
+            .await
+            .unwrap();
+
+        let pool = SqlitePool::connect_with(SqliteConnectOptions::new().filename(&database))
+            .await
+            .unwrap();
+        let months: Vec<String> = sqlx::query_scalar("SELECT month FROM budget")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(months, vec!["2025-03"]);
+        let status = month_budget_status(&pool, "2025-3").await.unwrap();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].budget, "400".parse().unwrap());
+    }
+
+    #[tokio::test]
+    async fn budget_month_must_be_a_month() {
+        let database = test_database("budget-invalid-month").await;
+        for month in ["2025-13", "2025-03-01", "march", ""] {
+            assert!(
+                budget_command(&set("300", month), database.clone())
+                    .await
+                    .is_err(),
+                "{month}"
+            );
+        }
+    }
+}
@@ -0,0 +1,302 @@
+use crate::cli_customs::{CategoryArgs, CategoryCommands, CategoryPathArgs, CategoryRenameArgs};
+use crate::money::Money;
+use crate::output::{print_notice, print_rows, print_table};
+use crate::process::Amount;
+use chrono::Utc;
+use colored::*;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::PathBuf;
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Panel,
+        object::Rows,
+        style::{Border, BorderColor, Style},
+        themes::Colorization,
+    },
+};
+
+/// the separator between a parent and a child category.
+pub const CATEGORY_SEPARATOR: char = ':';
+
+/// a category with the records directly in it.
+#[derive(FromRow, Clone, Debug)]
+struct CategoryUsage {
+    path: String,
+    records: i64,
+    total: Money,
+}
+
+/// a row of the category tree output.
+#[derive(Clone, Debug, Tabled, Serialize)]
+struct CategoryRow {
+    path: String,
+    records: i64,
+    total: Money,
+    in_or_out: bool,
+}
+
+/// dispatch the `category` subcommands.
+pub async fn category_command(
+    args: &CategoryArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    match &args.command {
+        CategoryCommands::Add(arg) => add_category(arg, &pool).await,
+        CategoryCommands::List => list_categories(&pool).await,
+        CategoryCommands::Rename(arg) => rename_category(arg, &pool).await,
+        CategoryCommands::Remove(arg) => remove_category(arg, &pool).await,
+    }
+}
+
+/// split a path like `Food:Groceries` into its parts.
+fn split_path(path: &str) -> Result<Vec<&str>, Box<dyn Error>> {
+    let parts: Vec<&str> = path.split(CATEGORY_SEPARATOR).map(str::trim).collect();
+    if parts.iter().any(|x| x.is_empty()) {
+        return Err(format!("invalid category path `{}`", path).into());
+    }
+    Ok(parts)
+}
+
+/// all the ancestors of a path including itself, e.g.
+/// `Food:Groceries` -> [`Food`, `Food:Groceries`].
+pub fn path_with_ancestors(path: &str) -> Vec<String> {
+    path.match_indices(CATEGORY_SEPARATOR)
+        .map(|(i, _)| path[..i].to_string())
+        .chain(std::iter::once(path.to_string()))
+        .collect()
+}
+
+/// the net total of every category in `amount`, rolled up to the parents.
+/// transfers and records without a category are left out.
+pub fn category_subtotals(amount: &[Amount]) -> Result<BTreeMap<String, Money>, String> {
+    let mut subtotals: BTreeMap<String, Money> = BTreeMap::new();
+    for record in amount.iter().filter(|x| x.transfer_id.is_none()) {
+        if let Some(ref category) = record.category {
+            for path in path_with_ancestors(category) {
+                let subtotal = subtotals.entry(path).or_default();
+                *subtotal = subtotal.checked_add(record.signed_amount())?;
+            }
+        }
+    }
+    Ok(subtotals)
+}
+
+/// find the child `name` of `parent_id`, `None` parent is the root.
+async fn find_child(
+    pool: &SqlitePool,
+    parent_id: Option<i64>,
+    name: &str,
+) -> Result<Option<i64>, Box<dyn Error>> {
+    Ok(sqlx::query_as::<_, (i64,)>(
+        "SELECT id FROM category WHERE ifnull(parent_id, 0) = ifnull(?, 0) AND name = ?",
+    )
+    .bind(parent_id)
+    .bind(name)
+    .fetch_optional(pool)
+    .await?
+    .map(|x| x.0))
+}
+
+/// resolve a category path to its id.
+pub async fn resolve_category(pool: &SqlitePool, path: &str) -> Result<i64, Box<dyn Error>> {
+    let mut parent_id = None;
+    for name in split_path(path)? {
+        parent_id = Some(find_child(pool, parent_id, name).await?.ok_or(format!(
+            "category `{}` does not exist, add it with `rustance category add`",
+            path
+        ))?);
+    }
+    Ok(parent_id.unwrap())
+}
+
+async fn add_category(args: &CategoryPathArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let parts = split_path(&args.path)?;
+    if resolve_category(pool, &args.path).await.is_ok() {
+        return Err(format!("category `{}` already exists", args.path).into());
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut parent_id: Option<i64> = None;
+    for name in parts {
+        let id = sqlx::query_as::<_, (i64,)>(
+            "SELECT id FROM category WHERE ifnull(parent_id, 0) = ifnull(?, 0) AND name = ?",
+        )
+        .bind(parent_id)
+        .bind(name)
+        .fetch_optional(&mut *tx)
+        .await?;
+        parent_id = Some(match id {
+            Some((id,)) => id,
+            None => sqlx::query(
+                "INSERT INTO category (name, parent_id, created_at, updated_at) VALUES (?, ?, ?, ?)",
+            )
+            .bind(name)
+            .bind(parent_id)
+            .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+            .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid(),
+        });
+    }
+    tx.commit().await?;
+
+    print_notice(format!(
+        "{}{}",
+        "Added category ".yellow().bold(),
+        args.path.purple().bold()
+    ));
+    Ok(())
+}
+
+async fn rename_category(
+    args: &CategoryRenameArgs,
+    pool: &SqlitePool,
+) -> Result<(), Box<dyn Error>> {
+    let new_name = args.new_name.trim();
+    if new_name.is_empty() || new_name.contains(CATEGORY_SEPARATOR) {
+        return Err(format!("invalid category name `{}`", args.new_name).into());
+    }
+    let id = resolve_category(pool, &args.path).await?;
+    let (parent_id,) =
+        sqlx::query_as::<_, (Option<i64>,)>("SELECT parent_id FROM category WHERE id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+    if find_child(pool, parent_id, new_name).await?.is_some() {
+        return Err(format!("category `{}` already exists", new_name).into());
+    }
+
+    sqlx::query("UPDATE category SET name = ?, updated_at = ? WHERE id = ?")
+        .bind(new_name)
+        .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    print_notice(format!(
+        "{}{}{}{}",
+        "Renamed category ".yellow().bold(),
+        args.path.purple().bold(),
+        " to ".yellow().bold(),
+        new_name.purple().bold()
+    ));
+    Ok(())
+}
+
+async fn remove_category(args: &CategoryPathArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let id = resolve_category(pool, &args.path).await?;
+    let (children,) =
+        sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM category WHERE parent_id = ?")
+            .bind(id)
+            .fetch_one(pool)
+            .await?;
+    if children > 0 {
+        return Err(format!("category `{}` still has children", args.path).into());
+    }
+    let (records, trashed) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT COUNT(*) - COUNT(deleted_at), COUNT(deleted_at) FROM amount_record WHERE category_id = ?",
+    )
+    .bind(id)
+    .fetch_one(pool)
+    .await?;
+    if records > 0 {
+        return Err(format!(
+            "category `{}` still has {} records, move them with `patch-record --category`",
+            args.path, records
+        )
+        .into());
+    }
+    if trashed > 0 {
+        return Err(format!(
+            "category `{}` still has {} records in the trash, remove them with `trash purge`",
+            args.path, trashed
+        )
+        .into());
+    }
+
+    sqlx::query("DELETE FROM category WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    print_notice(format!(
+        "{}{}",
+        "Removed category ".yellow().bold(),
+        args.path.purple().bold()
+    ));
+    Ok(())
+}
+
+/// list the category tree, the totals of children are rolled up to parents.
+async fn list_categories(pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let query = r#"
+        SELECT
+            category_path.path,
+            COUNT(amount_record.id) AS records,
+            COALESCE(SUM(CASE WHEN amount_record.in_or_out THEN amount_record.amount ELSE -amount_record.amount END), 0) AS total
+        FROM
+            category_path
+            LEFT JOIN amount_record
+                ON amount_record.category_id = category_path.id AND amount_record.deleted_at IS NULL
+        GROUP BY
+            category_path.id
+    "#;
+    let usage: Vec<CategoryUsage> = sqlx::query_as(query).fetch_all(pool).await?;
+    let mut tree: BTreeMap<String, (i64, Money)> = BTreeMap::new();
+    for x in &usage {
+        for path in path_with_ancestors(&x.path) {
+            let entry = tree.entry(path).or_default();
+            entry.0 += x.records;
+            entry.1 = entry.1.checked_add(x.total)?;
+        }
+    }
+
+    let rows: Vec<CategoryRow> = tree
+        .into_iter()
+        .map(|(path, (records, total))| CategoryRow {
+            path,
+            records,
+            total: total.abs(),
+            in_or_out: !total.is_negative(),
+        })
+        .collect();
+    if print_rows(&rows)? {
+        return Ok(());
+    }
+    if rows.is_empty() {
+        print_notice(
+            "No category yet, add one with `rustance category add <path>`."
+                .yellow()
+                .bold(),
+        );
+        return Ok(());
+    }
+
+    let table = Table::new(rows)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_BLUE,
+            Color::FG_BRIGHT_RED,
+        ]))
+        .with(Panel::header("Categories\n"))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .to_string();
+
+    print_table(table);
+
+    Ok(())
+}
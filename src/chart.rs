@@ -0,0 +1,306 @@
+use crate::cli_customs::{ChartArgs, ChartKind, OutputFormat};
+use crate::money::Money;
+use crate::output::{
+    output_format, print_csv, print_json, print_notice, print_table, terminal_width,
+};
+use crate::process::{Amount, select_range_records};
+use crate::report::PeriodSummary;
+use crate::time_utils::{DateRange, local_today, to_local};
+use chrono::{Datelike, Months, NaiveDate};
+use colored::*;
+use serde::Serialize;
+use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::path::PathBuf;
+
+/// the partial blocks of a bar, from one eighth to a full cell.
+const BLOCKS: [char; 8] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'];
+
+/// the heights of a sparkline, from one eighth to a full cell.
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// the outcome of a category in the period.
+#[derive(Clone, Debug, Serialize)]
+pub struct CategorySpend {
+    pub category: String,
+    pub outcome: Money,
+}
+
+/// the income and outcome of a day.
+#[derive(Clone, Debug, Serialize)]
+pub struct DaySpend {
+    /// the local date like `2025-03-02`.
+    pub date: String,
+    pub income: Money,
+    pub outcome: Money,
+}
+
+/// the data behind the charts, for the json output.
+#[derive(Serialize, Debug)]
+struct ChartReport {
+    period: String,
+    months: Vec<PeriodSummary>,
+    categories: Vec<CategorySpend>,
+    days: Vec<DaySpend>,
+}
+
+/// a bar of `value / max` of `width` cells, in eighths of a cell.
+/// a value above zero shows at least an eighth.
+fn bar(value: i64, max: i64, width: usize) -> String {
+    if value <= 0 || max <= 0 {
+        return String::new();
+    }
+    let eighths = (value as i128 * width as i128 * 8 / max as i128).max(1) as usize;
+    let mut bar = BLOCKS[7].to_string().repeat(eighths / 8);
+    if let Some(rest) = (eighths % 8).checked_sub(1) {
+        bar.push(BLOCKS[rest]);
+    }
+    bar
+}
+
+/// the sparkline cell of `value` against `max`, a dot for nothing.
+fn spark(value: i64, max: i64) -> char {
+    if value <= 0 || max <= 0 {
+        return '·';
+    }
+    SPARKS[((value as i128 * 8 - 1) / max as i128).min(7) as usize]
+}
+
+/// the first day of every month from `start` to `end`.
+fn months_between(start: NaiveDate, end: NaiveDate) -> Vec<NaiveDate> {
+    let mut month = start.with_day(1).unwrap();
+    let mut months = Vec::new();
+    while month <= end {
+        months.push(month);
+        match month.checked_add_months(Months::new(1)) {
+            Some(next) => month = next,
+            None => break,
+        }
+    }
+    months
+}
+
+/// the months of the chart, the open sides of the range end at the records.
+pub fn chart_months(range: &DateRange, records: &[Amount]) -> Vec<NaiveDate> {
+    let day = |x: &Amount| to_local(x.occurred_at).date_naive();
+    let start = range.start.or(records.first().map(day));
+    let end = range.end.or(records.last().map(day));
+    match (start, end) {
+        (Some(start), Some(end)) => months_between(start, end),
+        _ => Vec::new(),
+    }
+}
+
+/// the outcome of every category, the largest first.
+/// transfers are left out.
+pub fn category_spends(records: &[Amount]) -> Result<Vec<CategorySpend>, String> {
+    let mut spends: BTreeMap<String, Money> = BTreeMap::new();
+    for x in records
+        .iter()
+        .filter(|x| x.transfer_id.is_none() && !x.in_or_out)
+    {
+        let category = x.category.clone().unwrap_or("(no category)".to_string());
+        let spend = spends.entry(category).or_default();
+        *spend = spend.checked_add(x.amount)?;
+    }
+    let mut spends: Vec<CategorySpend> = spends
+        .into_iter()
+        .map(|(category, outcome)| CategorySpend { category, outcome })
+        .collect();
+    spends.sort_by_key(|x| std::cmp::Reverse(x.outcome.minor()));
+    Ok(spends)
+}
+
+/// the income and outcome of every day of `months`.
+/// transfers are left out.
+pub fn day_spends(months: &[NaiveDate], records: &[Amount]) -> Result<Vec<DaySpend>, String> {
+    let mut totals: HashMap<NaiveDate, (Money, Money)> = HashMap::new();
+    for x in records.iter().filter(|x| x.transfer_id.is_none()) {
+        let total = totals
+            .entry(to_local(x.occurred_at).date_naive())
+            .or_default();
+        match x.in_or_out {
+            true => total.0 = total.0.checked_add(x.amount)?,
+            false => total.1 = total.1.checked_add(x.amount)?,
+        }
+    }
+    Ok(months
+        .iter()
+        .flat_map(|first| {
+            first
+                .iter_days()
+                .take_while(move |x| x.month() == first.month())
+        })
+        .map(|date| {
+            let (income, outcome) = totals.get(&date).copied().unwrap_or_default();
+            DaySpend {
+                date: date.format("%Y-%m-%d").to_string(),
+                income,
+                outcome,
+            }
+        })
+        .collect())
+}
+
+/// draw the charts of the records in the period, all of them unless `--only` is given.
+pub async fn chart_command(args: &ChartArgs, database_path: PathBuf) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    let mut range = args.range.range()?;
+    if range.is_all() {
+        // the last twelve months by default.
+        let today = local_today();
+        range = DateRange::new(today.with_day(1).unwrap() - Months::new(11), today);
+    }
+    let records = select_range_records(&pool, &range).await?;
+    let months = chart_months(&range, &records);
+    let summaries: Vec<PeriodSummary> = months
+        .iter()
+        .map(|first| {
+            PeriodSummary::of(
+                first.format("%Y-%m").to_string(),
+                records.iter().filter(|x| {
+                    let day = to_local(x.occurred_at).date_naive();
+                    day.year() == first.year() && day.month() == first.month()
+                }),
+            )
+        })
+        .collect::<Result<_, _>>()?;
+    let categories = category_spends(&records)?;
+    let days = day_spends(&months, &records)?;
+
+    match output_format() {
+        OutputFormat::Json => {
+            return print_json(&ChartReport {
+                period: range.to_string(),
+                months: summaries,
+                categories,
+                days,
+            });
+        }
+        OutputFormat::Csv => return print_csv(&days),
+        OutputFormat::Table | OutputFormat::Plain => {}
+    }
+    if records.is_empty() {
+        print_notice("No record in the period.".yellow().bold());
+        return Ok(());
+    }
+
+    let width = terminal_width().max(40);
+    let shown = |kind| args.only.is_empty() || args.only.contains(&kind);
+    if shown(ChartKind::Months) {
+        print_table(month_chart(&summaries, width));
+    }
+    if shown(ChartKind::Categories) {
+        print_table(category_chart(&categories, width));
+    }
+    if shown(ChartKind::Days) {
+        print_table(day_chart(&months, &days)?);
+    }
+
+    Ok(())
+}
+
+/// a green income bar and a red outcome bar for every month.
+fn month_chart(summaries: &[PeriodSummary], width: usize) -> String {
+    let max = summaries
+        .iter()
+        .map(|x| x.income.minor().max(x.outcome.minor()))
+        .max()
+        .unwrap_or(0);
+    let value_width = summaries
+        .iter()
+        .map(|x| x.income.to_string().len().max(x.outcome.to_string().len()))
+        .max()
+        .unwrap_or(0);
+    // the month, the spaces and the value beside the bar.
+    let bar_width = width.saturating_sub(7 + 3 + value_width).max(10);
+
+    let mut lines = vec![format!(
+        "{}  {} {}",
+        "Income And Outcome By Month".yellow().bold(),
+        "income".green(),
+        "outcome".red()
+    )];
+    for x in summaries {
+        lines.push(format!(
+            "{} {} {}",
+            x.period.bright_white(),
+            bar(x.income.minor(), max, bar_width).green(),
+            x.income.to_string().green()
+        ));
+        lines.push(format!(
+            "{} {} {}",
+            " ".repeat(x.period.chars().count()),
+            bar(x.outcome.minor(), max, bar_width).red(),
+            x.outcome.to_string().red()
+        ));
+    }
+    lines.join("\n")
+}
+
+/// a red bar for the outcome of every category.
+fn category_chart(categories: &[CategorySpend], width: usize) -> String {
+    let mut lines = vec!["Outcome By Category".yellow().bold().to_string()];
+    if categories.is_empty() {
+        lines.push("No outcome in the period.".to_string());
+        return lines.join("\n");
+    }
+    let max = categories[0].outcome.minor();
+    let total: i64 = categories.iter().map(|x| x.outcome.minor()).sum();
+    let name_width = categories
+        .iter()
+        .map(|x| x.category.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(width / 3);
+    let value_width = categories[0].outcome.to_string().len();
+    // the name, the spaces, the value and its share like ` (12.3%)`.
+    let bar_width = width
+        .saturating_sub(name_width + 3 + value_width + 9)
+        .max(10);
+
+    for x in categories {
+        let name: String = x.category.chars().take(name_width).collect();
+        lines.push(format!(
+            "{} {} {} {}",
+            format!("{:<width$}", name, width = name_width).bright_cyan(),
+            bar(x.outcome.minor(), max, bar_width).red(),
+            x.outcome.to_string().red(),
+            format!("({:.1}%)", x.outcome.minor() as f64 * 100.0 / total as f64).bright_yellow()
+        ));
+    }
+    lines.join("\n")
+}
+
+/// a sparkline of the daily outcome for every month, scaled to the busiest day.
+fn day_chart(months: &[NaiveDate], days: &[DaySpend]) -> Result<String, String> {
+    let max = days.iter().map(|x| x.outcome.minor()).max().unwrap_or(0);
+    let mut lines = vec![format!(
+        "{}  {}{}",
+        "Daily Outcome".yellow().bold(),
+        "busiest day: ".bright_white(),
+        Money::from_minor(max).to_string().red()
+    )];
+    for first in months {
+        let prefix = first.format("%Y-%m").to_string();
+        let month: Vec<&DaySpend> = days
+            .iter()
+            .filter(|x| x.date.starts_with(&prefix))
+            .collect();
+        let line: String = month
+            .iter()
+            .map(|x| spark(x.outcome.minor(), max))
+            .collect();
+        let total = Money::total(month.iter().map(|x| x.outcome))?;
+        lines.push(format!(
+            "{} {} {}",
+            prefix.bright_white(),
+            format!("{:<31}", line).red(),
+            total.to_string().red()
+        ));
+    }
+    Ok(lines.join("\n"))
+}
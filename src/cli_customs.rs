@@ -1,4 +1,9 @@
-use clap::{Args, Parser, Subcommand};
+use crate::money::{Money, parse_amount};
+use crate::time_utils::{
+    DateRange, local_today, parse_date, parse_days, parse_month, parse_quarter, parse_week,
+};
+use chrono::{Duration, Months, NaiveDate};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 /// Cli styles
 pub fn get_styles() -> clap::builder::Styles {
@@ -48,10 +53,32 @@ pub fn get_styles() -> clap::builder::Styles {
 #[command(styles=get_styles())]
 #[command(propagate_version = true)]
 pub struct Cli {
+    /// the timezone to use, overrides the config file.
+    /// an IANA name like Europe/Berlin.
+    #[arg(long, global = true)]
+    pub tz: Option<String>,
+
+    /// the output format. json and csv write the records or the rows
+    /// of the tables to stdout, the messages for humans go to stderr.
+    /// colors are only used for tables in a terminal.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Table)]
+    pub output: OutputFormat,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// colored tables.
+    #[default]
+    Table,
+    /// tables without colors.
+    Plain,
+    Json,
+    Csv,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// List all the Wallet Balances.
@@ -66,8 +93,66 @@ pub enum Commands {
     /// patch record.
     PatchRecord(PatchRecordArgs),
 
-    /// delete record.
+    /// delete record, it stays in the trash until it is purged.
     DeleteRecord(DeleteRecordArgs),
+
+    /// manage the accounts the money lives in.
+    Account(AccountArgs),
+
+    /// move money between accounts.
+    /// it is neither income nor outcome.
+    Transfer(TransferArgs),
+
+    /// manage the categories of records.
+    Category(CategoryArgs),
+
+    /// report the tags of records.
+    Tag(TagArgs),
+
+    /// manage the monthly budgets.
+    Budget(BudgetArgs),
+
+    /// manage the recurring records, e.g. rent, salary, subscriptions.
+    Recurring(RecurringArgs),
+
+    /// export the records to csv or json.
+    Export(ExportArgs),
+
+    /// import records from files.
+    Import(ImportArgs),
+
+    /// review the suspected duplicate records and merge them.
+    Dedupe(DedupeArgs),
+
+    /// undo the last changes of the records.
+    Undo(UndoArgs),
+
+    /// redo the changes which are undone.
+    Redo(RedoArgs),
+
+    /// list the recent changes of the records.
+    History(HistoryArgs),
+
+    /// manage the deleted records.
+    Trash(TrashArgs),
+
+    /// show a record, with its changes by `--history`.
+    Show(ShowArgs),
+
+    /// manage the hash chain of the changes, which makes tampering evident.
+    Ledger(LedgerArgs),
+
+    /// check the records and the hash chain for tampering.
+    Verify(VerifyArgs),
+
+    /// summarize the records of a longer period.
+    Report(ReportArgs),
+
+    /// draw charts of the income and outcome in the terminal.
+    Chart(ChartArgs),
+
+    /// search the messages of the records.
+    Search(SearchArgs),
 }
 
 #[derive(Args)]
@@ -79,39 +164,315 @@ pub struct ListAllArgs {
     // /// The verbose output will list all the addtional msg.
     // #[arg(short, long, default_value_t = false)]
     // pub verbose: bool,
-    /// Time select.
+    #[command(flatten)]
+    pub filter: RecordFilterArgs,
+}
+
+/// the filters selecting records, shared by the commands reading them.
+#[derive(Args)]
+pub struct RecordFilterArgs {
+    #[command(flatten)]
+    pub range: DateRangeArgs,
+
+    /// only list the records of this account.
+    #[arg(long)]
+    pub account: Option<String>,
+
+    /// only list the records with all of these tags.
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// leave out the records with any of these tags.
+    #[arg(long = "not-tag")]
+    pub not_tags: Vec<String>,
+}
+
+/// the period of the records, only one of them can be chosen.
+/// `--from` and `--to` can be given together.
+#[derive(Args, Default)]
+#[command(next_help_heading = "Period")]
+pub struct DateRangeArgs {
+    /// only the month, e.g. 2025-03.
+    #[arg(short = 't', long, alias = "time", value_parser = parse_month)]
+    pub month: Option<NaiveDate>,
+
+    /// only this month.
+    #[arg(long, default_value_t = false)]
+    pub this_month: bool,
+
+    /// only the month before this one.
+    #[arg(long, default_value_t = false)]
+    pub last_month: bool,
+
+    /// only the iso week from monday, e.g. 2025-W10.
+    #[arg(long, value_parser = parse_week)]
+    pub week: Option<NaiveDate>,
+
+    /// only this week.
+    #[arg(long, default_value_t = false)]
+    pub this_week: bool,
+
+    /// only the week before this one.
+    #[arg(long, default_value_t = false)]
+    pub last_week: bool,
+
+    /// only the quarter, e.g. 2025-Q1.
+    #[arg(long, value_parser = parse_quarter)]
+    pub quarter: Option<NaiveDate>,
+
+    /// only this quarter.
+    #[arg(long, default_value_t = false)]
+    pub this_quarter: bool,
+
+    /// only the quarter before this one, in the last year for the first quarter.
+    #[arg(long, default_value_t = false)]
+    pub last_quarter: bool,
+
+    /// only the year, e.g. 2025.
+    #[arg(long)]
+    pub year: Option<i32>,
+
+    /// only the last days up to today, e.g. 90d or 4w.
+    #[arg(long, value_parser = parse_days)]
+    pub last: Option<i64>,
+
+    /// only the records on or after this local date, e.g. 2025-03-02.
+    #[arg(long, value_parser = parse_date)]
+    pub from: Option<NaiveDate>,
+
+    /// only the records on or before this local date.
+    #[arg(long, value_parser = parse_date)]
+    pub to: Option<NaiveDate>,
+}
+
+impl DateRangeArgs {
+    /// the chosen period, all the time if none is chosen.
+    pub fn range(&self) -> Result<DateRange, String> {
+        let today = local_today();
+        let mut ranges: Vec<DateRange> = [
+            (self.this_month, DateRange::month(today)),
+            (self.last_month, DateRange::month(today - Months::new(1))),
+            (self.this_week, DateRange::week(today)),
+            (self.last_week, DateRange::week(today - Duration::days(7))),
+            (self.this_quarter, DateRange::quarter(today)),
+            (
+                self.last_quarter,
+                DateRange::quarter(today - Months::new(3)),
+            ),
+        ]
+        .into_iter()
+        .filter(|x| x.0)
+        .map(|x| x.1)
+        .collect::<Result<_, _>>()?;
+        if let Some(x) = self.month {
+            ranges.push(DateRange::month(x)?);
+        }
+        if let Some(x) = self.week {
+            ranges.push(DateRange::week(x)?);
+        }
+        if let Some(x) = self.quarter {
+            ranges.push(DateRange::quarter(x)?);
+        }
+        if let Some(x) = self.year {
+            let first =
+                NaiveDate::from_ymd_opt(x, 1, 1).ok_or_else(|| format!("invalid year `{}`", x))?;
+            ranges.push(DateRange::year(first)?);
+        }
+        if let Some(x) = self.last {
+            if x < 1 {
+                return Err("--last must be at least 1 day".into());
+            }
+            ranges.push(DateRange::last_days(x)?);
+        }
+        if self.from.is_some() || self.to.is_some() {
+            ranges.push(DateRange {
+                start: self.from,
+                end: self.to,
+            });
+        }
+
+        match ranges[..] {
+            [] => Ok(DateRange::default()),
+            [range] => match (range.start, range.end) {
+                (Some(start), Some(end)) if start > end => {
+                    Err(format!("the period {} ends before it starts", range))
+                }
+                _ => Ok(range),
+            },
+            _ => Err("choose only one period, e.g. --month, --last or --from/--to".into()),
+        }
+    }
+
+    /// whether no period is chosen.
+    pub fn is_empty(&self) -> bool {
+        self.range().is_ok_and(|x| x.is_all())
+    }
+}
+
+/// the filters selecting the records to delete or patch,
+/// a record must match the ids and all of them.
+#[derive(Args, Default)]
+pub struct RecordSelectArgs {
+    #[command(flatten)]
+    pub range: DateRangeArgs,
+
+    /// only the records whose message matches this regex, e.g. "(?i)starbucks".
+    #[arg(long = "match")]
+    pub pattern: Option<String>,
+
+    /// only the records with at least this amount.
+    #[arg(long, value_parser = parse_amount)]
+    pub min: Option<Money>,
+
+    /// only the records with at most this amount.
+    #[arg(long, value_parser = parse_amount)]
+    pub max: Option<Money>,
+}
+
+impl RecordSelectArgs {
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty() && self.pattern.is_none() && self.min.is_none() && self.max.is_none()
+    }
+}
+
+/// the ids `start..=end`, a single id has the same start and end.
+#[derive(Clone, Copy, Debug)]
+pub struct IdRange {
+    pub start: i32,
+    pub end: i32,
+}
+
+/// clap value parser for an id like `12` or an id range like `3-9`.
+fn parse_id_range(input: &str) -> Result<IdRange, String> {
+    let parse = |x: &str| {
+        x.trim()
+            .parse::<i32>()
+            .map_err(|_| format!("invalid id `{}`", x.trim()))
+    };
+    let (start, end) = match input.split_once('-') {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => (parse(input)?, parse(input)?),
+    };
+    if start > end {
+        return Err(format!(
+            "invalid id range `{}`, the start is after the end",
+            input
+        ));
+    }
+    Ok(IdRange { start, end })
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    #[command(flatten)]
+    pub filter: RecordFilterArgs,
+
+    /// the format to write, json with `--output json`, else csv if not set.
+    #[arg(long, value_enum)]
+    pub format: Option<ExportFormat>,
+
+    /// the file to write, stdout if not set.
     #[arg(short, long)]
-    pub time: Option<String>,
+    pub file: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    /// one json record per line.
+    Ndjson,
 }
 
 #[derive(Args)]
 pub struct AmountArgs {
     /// the amount of money. e.g. 100.00.
     /// The amount must be greater than 0.
-    /// and the digit after the decimal point must be less than 2.
-    pub amount: f32,
+    /// and the digit after the decimal point must be no more than 2.
+    #[arg(value_parser = parse_amount)]
+    pub amount: Money,
 
     /// add if some additional message is needed.
     pub add_msg: Option<String>,
+
+    /// the date the money moved, defaults to now.
+    /// e.g. 2025-03-02, "2025-03-02 18:30", today, yesterday.
+    #[arg(short, long)]
+    pub date: Option<String>,
+
+    /// the account the money moved in.
+    /// defaults to the first account which is not archived.
+    #[arg(long)]
+    pub account: Option<String>,
+
+    /// the category path of the record, e.g. Food:Groceries.
+    #[arg(short, long)]
+    pub category: Option<String>,
+
+    /// tag the record, can be repeated.
+    /// `#hashtags` inside the message are tags too.
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct TransferArgs {
+    /// the account the money leaves.
+    pub from: String,
+
+    /// the account the money goes to.
+    pub to: String,
+
+    /// the amount of money. e.g. 100.00.
+    /// The amount must be greater than 0.
+    /// and the digit after the decimal point must be no more than 2.
+    #[arg(value_parser = parse_amount)]
+    pub amount: Money,
+
+    /// add if some additional message is needed.
+    pub add_msg: Option<String>,
+
+    /// the date the money moved, defaults to now.
+    /// e.g. 2025-03-02, "2025-03-02 18:30", today, yesterday.
+    #[arg(short, long)]
+    pub date: Option<String>,
 }
 
 #[derive(Args)]
 pub struct DeleteRecordArgs {
-    /// the id of the record.
-    /// will show the id of the record before delete.
-    pub id: i32,
+    /// the ids of the records, e.g. 12, 3-9 or 4,5,6.
+    /// will show the records before delete.
+    /// both sides of a transfer are deleted together.
+    #[arg(value_delimiter = ',', value_parser = parse_id_range)]
+    pub ids: Vec<IdRange>,
+
+    #[command(flatten)]
+    pub select: RecordSelectArgs,
+
+    /// delete without asking for confirmation.
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
+
+    /// only show the records which would be deleted.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
 }
 
 #[derive(Args)]
 pub struct PatchRecordArgs {
-    /// the id of the record.
-    pub id: i32,
+    /// the ids of the records, e.g. 12, 3-9 or 4,5,6.
+    /// amount, message and date of a transfer are patched on both sides.
+    #[arg(value_delimiter = ',', value_parser = parse_id_range)]
+    pub ids: Vec<IdRange>,
+
+    #[command(flatten)]
+    pub select: RecordSelectArgs,
 
     /// the amount of money. e.g. 100.00.
     /// The amount must be greater than 0.
-    /// and the digit after the decimal point must be less than 2.
-    #[arg(long)]
-    pub amount: Option<f32>,
+    /// and the digit after the decimal point must be no more than 2.
+    #[arg(long, value_parser = parse_amount)]
+    pub amount: Option<Money>,
 
     /// the in or out of the record.
     /// true means income, false means outcome.
@@ -121,4 +482,673 @@ pub struct PatchRecordArgs {
     /// add if some additional message is needed.
     #[arg(short, long)]
     pub add_msg: Option<String>,
+
+    /// move the record to another date.
+    /// e.g. 2025-03-02, "2025-03-02 18:30", today, yesterday.
+    #[arg(short, long)]
+    pub date: Option<String>,
+
+    /// move the record to another account.
+    #[arg(long)]
+    pub account: Option<String>,
+
+    /// move the record to another category, e.g. Food:Groceries.
+    /// an empty string removes the category.
+    #[arg(short, long)]
+    pub category: Option<String>,
+
+    /// add a tag to the record, can be repeated.
+    /// `#hashtags` inside a new message are added too.
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// remove a tag from the record, can be repeated.
+    #[arg(long = "untag")]
+    pub untags: Vec<String>,
+
+    /// patch without asking for confirmation.
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
+
+    /// only show the records before and after the patch.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct AccountArgs {
+    #[command(subcommand)]
+    pub command: AccountCommands,
+}
+
+#[derive(Subcommand)]
+pub enum AccountCommands {
+    /// add a new account, e.g. cash, bank-card, alipay.
+    Add(AccountNameArgs),
+
+    /// list the accounts with their balances.
+    List(AccountListArgs),
+
+    /// rename an account.
+    Rename(AccountRenameArgs),
+
+    /// archive an account, it can not take new records any more.
+    Archive(AccountNameArgs),
+}
+
+#[derive(Args)]
+pub struct AccountNameArgs {
+    /// the name of the account.
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct AccountListArgs {
+    /// also list the archived accounts.
+    #[arg(long, default_value_t = false)]
+    pub all: bool,
+}
+
+#[derive(Args)]
+pub struct AccountRenameArgs {
+    /// the current name of the account.
+    pub name: String,
+
+    /// the new name of the account.
+    pub new_name: String,
+}
+
+#[derive(Args)]
+pub struct CategoryArgs {
+    #[command(subcommand)]
+    pub command: CategoryCommands,
+}
+
+#[derive(Subcommand)]
+pub enum CategoryCommands {
+    /// add a category, missing parents are added too.
+    /// e.g. Food:Groceries.
+    Add(CategoryPathArgs),
+
+    /// list the category tree with the totals rolled up to parents.
+    List,
+
+    /// rename a category, its children follow it.
+    Rename(CategoryRenameArgs),
+
+    /// remove a category which has no children and no records.
+    Remove(CategoryPathArgs),
+}
+
+#[derive(Args)]
+pub struct CategoryPathArgs {
+    /// the path of the category, parents separated by `:`.
+    pub path: String,
+}
+
+#[derive(Args)]
+pub struct CategoryRenameArgs {
+    /// the path of the category, parents separated by `:`.
+    pub path: String,
+
+    /// the new name of the last part of the path.
+    pub new_name: String,
+}
+
+#[derive(Args)]
+pub struct TagArgs {
+    #[command(subcommand)]
+    pub command: TagCommands,
+}
+
+#[derive(Subcommand)]
+pub enum TagCommands {
+    /// list the tags with how many records they have.
+    List,
+
+    /// the income, outcome and net total of every tag.
+    Summary(TagSummaryArgs),
+}
+
+#[derive(Args)]
+pub struct TagSummaryArgs {
+    #[command(flatten)]
+    pub range: DateRangeArgs,
+}
+
+#[derive(Args)]
+pub struct BudgetArgs {
+    #[command(subcommand)]
+    pub command: BudgetCommands,
+}
+
+#[derive(Subcommand)]
+pub enum BudgetCommands {
+    /// set the budget of a category or the overall budget.
+    /// a budget of a single month overrides the recurring one.
+    Set(BudgetSetArgs),
+
+    /// list the budgets with the spending of the month.
+    List(BudgetListArgs),
+
+    /// remove a budget.
+    Remove(BudgetTargetArgs),
+}
+
+#[derive(Args)]
+pub struct BudgetSetArgs {
+    /// the most money to spend in a month. e.g. 2000.00.
+    #[arg(value_parser = parse_amount)]
+    pub amount: Money,
+
+    #[command(flatten)]
+    pub target: BudgetTargetArgs,
+}
+
+#[derive(Args)]
+pub struct BudgetTargetArgs {
+    /// the category of the budget, its children count too.
+    /// the overall budget if not set.
+    #[arg(short, long)]
+    pub category: Option<String>,
+
+    /// only for this month, e.g. 2025-03.
+    /// the budget recurs every month if not set.
+    #[arg(short, long)]
+    pub month: Option<String>,
+}
+
+#[derive(Args)]
+pub struct BudgetListArgs {
+    /// the month to compare the spending with, defaults to this month.
+    #[arg(short, long)]
+    pub month: Option<String>,
+}
+
+#[derive(Args)]
+pub struct RecurringArgs {
+    #[command(subcommand)]
+    pub command: RecurringCommands,
+}
+
+#[derive(Subcommand)]
+pub enum RecurringCommands {
+    /// add a recurring record.
+    Add(RecurringAddArgs),
+
+    /// list the recurring records with their next date.
+    List,
+
+    /// remove a recurring record, the records already added are kept.
+    Remove(RecurringRemoveArgs),
+
+    /// add all the due records since the last run.
+    /// it also runs before the commands reading or writing records.
+    Run(RecurringRunArgs),
+}
+
+#[derive(Args)]
+pub struct RecurringAddArgs {
+    /// the amount of money. e.g. 100.00.
+    /// The amount must be greater than 0.
+    /// and the digit after the decimal point must be no more than 2.
+    #[arg(value_parser = parse_amount)]
+    pub amount: Money,
+
+    /// add if some additional message is needed.
+    pub add_msg: Option<String>,
+
+    /// it is an income, an outcome if not set.
+    #[arg(long, default_value_t = false)]
+    pub income: bool,
+
+    /// how often it recurs, e.g. day, week, "2 weeks", month, year.
+    #[arg(short, long, default_value = "month")]
+    pub every: String,
+
+    /// the date of the first record, which also fixes the day of the
+    /// later ones, e.g. 2025-03-05 for monthly on the 5th. defaults to today.
+    #[arg(short, long)]
+    pub start: Option<String>,
+
+    /// the last date a record can be added, e.g. 2026-12-31.
+    #[arg(long)]
+    pub end: Option<String>,
+
+    /// the account the money moves in.
+    /// defaults to the first account which is not archived.
+    #[arg(long)]
+    pub account: Option<String>,
+
+    /// the category path of the records, e.g. Housing:Rent.
+    #[arg(short, long)]
+    pub category: Option<String>,
+
+    /// tag the records, can be repeated.
+    /// `#hashtags` inside the message are tags too.
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct RecurringRemoveArgs {
+    /// the id of the recurring record.
+    pub id: i64,
+}
+
+#[derive(Args)]
+pub struct RecurringRunArgs {
+    /// only show the records which would be added.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+
+    /// add the records due until this date, defaults to today.
+    /// e.g. 2025-12-31.
+    #[arg(long)]
+    pub until: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    #[command(subcommand)]
+    pub command: ImportCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ImportCommands {
+    /// import a csv file, e.g. a spreadsheet or a bank export.
+    /// a column is either its header or its 1-based position.
+    Csv(ImportCsvArgs),
+
+    /// import a bill exported from Alipay (支付宝), GBK or UTF-8.
+    /// closed trades are skipped, 不计收支 becomes a transfer.
+    Alipay(ImportBillArgs),
+
+    /// import a csv bill exported from WeChat Pay (微信支付).
+    /// closed trades are skipped, 不计收支 becomes a transfer.
+    Wechat(ImportBillArgs),
+
+    /// import a file written by `rustance export`.
+    /// the accounts and categories in it must exist.
+    /// the records already imported from it are skipped.
+    Rustance(ImportRustanceArgs),
+}
+
+#[derive(Args)]
+pub struct ImportCsvArgs {
+    /// the csv file to import.
+    pub file: String,
+
+    /// use a mapping profile saved in the config file under
+    /// `[import_profiles.<name>]`, the options below override it.
+    #[arg(short, long)]
+    pub profile: Option<String>,
+
+    /// the column of a signed amount, positive is income.
+    #[arg(long)]
+    pub amount: Option<String>,
+
+    /// the column of the money going out, used with `--credit`.
+    #[arg(long)]
+    pub debit: Option<String>,
+
+    /// the column of the money coming in, used with `--debit`.
+    #[arg(long)]
+    pub credit: Option<String>,
+
+    /// the column of the date.
+    #[arg(long)]
+    pub date: Option<String>,
+
+    /// the column of an id like a transaction number, rows with an id
+    /// already imported with the same profile are skipped,
+    /// or from a file of the same name without a profile.
+    #[arg(long)]
+    pub id: Option<String>,
+
+    /// the format of the date, e.g. %d.%m.%Y.
+    /// common formats are tried if not set.
+    #[arg(long)]
+    pub date_format: Option<String>,
+
+    /// the column of the message, can be repeated to join several.
+    #[arg(long)]
+    pub msg: Vec<String>,
+
+    /// the decimal separator, `.` or `,`. defaults to `.`.
+    #[arg(long)]
+    pub decimal: Option<char>,
+
+    /// the field delimiter. defaults to `,`.
+    #[arg(long)]
+    pub delimiter: Option<char>,
+
+    /// skip this many lines before the header.
+    #[arg(long)]
+    pub skip: Option<usize>,
+
+    /// positive amounts are outcome, like in credit card exports.
+    #[arg(long, default_value_t = false)]
+    pub negate: bool,
+
+    /// the account the records go into.
+    /// defaults to the first account which is not archived.
+    #[arg(long)]
+    pub account: Option<String>,
+
+    /// the category path of all the records, e.g. Food.
+    #[arg(short, long)]
+    pub category: Option<String>,
+
+    /// tag all the records, can be repeated.
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// leave out the suspected duplicates of existing records.
+    #[arg(long, default_value_t = false)]
+    pub skip_duplicates: bool,
+
+    /// records this many days apart can be duplicates.
+    #[arg(long, default_value_t = 3)]
+    pub within_days: i64,
+
+    /// import without asking for confirmation.
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
+
+    /// only show the preview.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct ImportBillArgs {
+    /// the bill file to import.
+    pub file: String,
+
+    /// the account the records go into.
+    /// defaults to the first account which is not archived.
+    #[arg(long)]
+    pub account: Option<String>,
+
+    /// the other account of the 不计收支 trades, e.g. a bank card.
+    /// they are imported as transfers with it.
+    #[arg(long)]
+    pub transfer_account: Option<String>,
+
+    /// the category path of all the records, e.g. Daily.
+    #[arg(short, long)]
+    pub category: Option<String>,
+
+    /// tag all the records, can be repeated.
+    #[arg(long = "tag")]
+    pub tags: Vec<String>,
+
+    /// leave out the suspected duplicates of existing records.
+    #[arg(long, default_value_t = false)]
+    pub skip_duplicates: bool,
+
+    /// records this many days apart can be duplicates.
+    #[arg(long, default_value_t = 3)]
+    pub within_days: i64,
+
+    /// import without asking for confirmation.
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
+
+    /// only show the preview.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct DedupeArgs {
+    /// records this many days apart can be duplicates.
+    #[arg(long, default_value_t = 3)]
+    pub within_days: i64,
+}
+
+#[derive(Args)]
+pub struct UndoArgs {
+    /// how many changes to undo.
+    #[arg(default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub count: u32,
+}
+
+#[derive(Args)]
+pub struct RedoArgs {
+    /// how many changes to redo.
+    #[arg(default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub count: u32,
+}
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// how many changes to list.
+    #[arg(short = 'n', long, default_value_t = 20, value_parser = clap::value_parser!(u32).range(1..))]
+    pub limit: u32,
+}
+
+#[derive(Args)]
+pub struct ShowArgs {
+    /// the id of the record, it can be in the trash or purged.
+    pub id: i32,
+
+    /// list every change of the record with the old and new values.
+    #[arg(long, default_value_t = false)]
+    pub history: bool,
+}
+
+#[derive(Args)]
+pub struct TrashArgs {
+    #[command(subcommand)]
+    pub command: TrashCommands,
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommands {
+    /// list the records in the trash.
+    List,
+
+    /// bring records back from the trash.
+    /// both sides of a transfer are restored together.
+    Restore(TrashRestoreArgs),
+
+    /// remove the records in the trash permanently.
+    Purge(TrashPurgeArgs),
+}
+
+#[derive(Args)]
+pub struct TrashRestoreArgs {
+    /// the ids of the records, e.g. 12, 3-9 or 4,5,6.
+    #[arg(required = true, value_delimiter = ',', value_parser = parse_id_range)]
+    pub ids: Vec<IdRange>,
+}
+
+#[derive(Args)]
+pub struct TrashPurgeArgs {
+    /// only purge the records deleted before this long ago, e.g. 30d or 4w.
+    #[arg(long, value_parser = parse_days)]
+    pub older_than: Option<i64>,
+
+    /// purge without asking for confirmation.
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
+}
+
+#[derive(Args)]
+pub struct ImportRustanceArgs {
+    /// the exported file to import.
+    pub file: String,
+
+    /// the format of the file, guessed from the extension if not set.
+    #[arg(long, value_enum)]
+    pub format: Option<ExportFormat>,
+
+    /// leave out the suspected duplicates of existing records.
+    #[arg(long, default_value_t = false)]
+    pub skip_duplicates: bool,
+
+    /// records this many days apart can be duplicates.
+    #[arg(long, default_value_t = 3)]
+    pub within_days: i64,
+
+    /// import without asking for confirmation.
+    #[arg(short, long, default_value_t = false)]
+    pub yes: bool,
+
+    /// only show the preview.
+    #[arg(long, default_value_t = false)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct LedgerArgs {
+    #[command(subcommand)]
+    pub command: LedgerCommands,
+}
+
+#[derive(Subcommand)]
+pub enum LedgerCommands {
+    /// start the hash chain with the records as they are now.
+    /// every change of the records is appended to it since.
+    Enable,
+
+    /// print the head of the chain as json, to keep it somewhere else.
+    Head(LedgerHeadArgs),
+}
+
+#[derive(Args)]
+pub struct LedgerHeadArgs {
+    /// write the head to the file instead.
+    #[arg(short, long)]
+    pub file: Option<String>,
+}
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// a head hash kept before, it must still be in the chain.
+    #[arg(long)]
+    pub head: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ReportArgs {
+    #[command(subcommand)]
+    pub command: ReportCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    /// the income, outcome, net and savings rate of every month of a year.
+    Year(ReportYearArgs),
+
+    /// write a static html report with charts, to open in a browser.
+    Html(ReportHtmlArgs),
+}
+
+#[derive(Args)]
+pub struct ReportYearArgs {
+    /// the year, e.g. 2025, defaults to this year.
+    pub year: Option<i32>,
+
+    /// compare every month with the year before.
+    #[arg(long, default_value_t = false)]
+    pub compare: bool,
+}
+
+#[derive(Args)]
+pub struct ReportHtmlArgs {
+    /// the period defaults to this month.
+    #[command(flatten)]
+    pub range: DateRangeArgs,
+
+    /// the file to write, rustance-report.html if not set.
+    #[arg(short, long)]
+    pub file: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ChartArgs {
+    /// the period defaults to the last twelve months.
+    #[command(flatten)]
+    pub range: DateRangeArgs,
+
+    /// only draw these charts, e.g. months,days.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub only: Vec<ChartKind>,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum ChartKind {
+    /// the income and outcome of every month.
+    Months,
+    /// the outcome of every category.
+    Categories,
+    /// the outcome of every day.
+    Days,
+}
+
+#[derive(Args)]
+pub struct SearchArgs {
+    /// the words to search, all of them must match.
+    /// e.g. coffee, coff* for a prefix, '"coffee beans"' for a phrase, "tea OR coffee".
+    /// words without ascii letters or digits, like 咖啡, match any part of a message.
+    pub query: String,
+
+    #[command(flatten)]
+    pub range: DateRangeArgs,
+
+    /// only the records with at least this amount.
+    #[arg(long, value_parser = parse_amount)]
+    pub min: Option<Money>,
+
+    /// only the records with at most this amount.
+    #[arg(long, value_parser = parse_amount)]
+    pub max: Option<Money>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(input: &str) -> Result<(i32, i32), String> {
+        parse_id_range(input).map(|x| (x.start, x.end))
+    }
+
+    #[test]
+    fn parse_a_single_id() {
+        assert_eq!(ids("12"), Ok((12, 12)));
+        assert_eq!(ids(" 7 "), Ok((7, 7)));
+    }
+
+    #[test]
+    fn parse_an_id_range() {
+        assert_eq!(ids("3-9"), Ok((3, 9)));
+        assert_eq!(ids("4 - 4"), Ok((4, 4)));
+    }
+
+    #[test]
+    fn parse_rejects_invalid_ids() {
+        assert_eq!(
+            ids("9-3"),
+            Err("invalid id range `9-3`, the start is after the end".to_string())
+        );
+        assert_eq!(ids("a"), Err("invalid id `a`".to_string()));
+        assert_eq!(ids("3-"), Err("invalid id ``".to_string()));
+        assert!(ids("").is_err());
+        assert!(ids("1-2-3").is_err());
+        assert!(ids("-3").is_err());
+    }
+
+    #[test]
+    fn undo_and_redo_count_at_least_one() {
+        for command in ["undo", "redo"] {
+            let parse = |count: &str| Cli::try_parse_from(["rustance", command, "--", count]);
+            assert!(parse("-1").is_err());
+            assert!(parse("0").is_err());
+            assert!(parse("3").is_ok());
+        }
+        assert!(Cli::try_parse_from(["rustance", "history", "-n", "0"]).is_err());
+    }
 }
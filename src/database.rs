@@ -1,3 +1,5 @@
+use crate::output::print_notice;
+use crate::time_utils::{DEFAULT_TIMEZONE, parse_timezone, set_timezone};
 use colored::*;
 use dirs::home_dir;
 use serde::Deserialize;
@@ -6,6 +8,7 @@ use sqlx::{
     migrate::{MigrateDatabase, Migrator},
     sqlite::SqlitePoolOptions,
 };
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::{error::Error, path::PathBuf};
@@ -13,6 +16,11 @@ use std::{error::Error, path::PathBuf};
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub database_config: DatabaseConfig,
+    #[serde(default)]
+    pub time_config: TimeConfig,
+    /// the saved column mappings of `import csv`, by profile name.
+    #[serde(default)]
+    pub import_profiles: HashMap<String, CsvProfile>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -21,10 +29,120 @@ pub struct DatabaseConfig {
     pub migrates: String,
 }
 
+#[derive(Debug, Deserialize, Default)]
+pub struct TimeConfig {
+    /// IANA timezone name, e.g. `Europe/Berlin`.
+    /// Month boundaries and displayed times follow it.
+    pub timezone: Option<String>,
+}
+
+/// How the columns of a csv file map to a record.
+/// A column is either its header or its 1-based position.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct CsvProfile {
+    /// a signed amount, positive is income.
+    pub amount: Option<String>,
+    /// the money going out, used with `credit` instead of `amount`.
+    pub debit: Option<String>,
+    /// the money coming in, used with `debit` instead of `amount`.
+    pub credit: Option<String>,
+    pub date: Option<String>,
+    /// the id of the record in the file, makes a re-import skip it.
+    pub id: Option<String>,
+    /// a chrono format like `%d.%m.%Y`, common formats are tried if not set.
+    pub date_format: Option<String>,
+    /// the columns joined into the message.
+    #[serde(default)]
+    pub msg: Vec<String>,
+    /// the decimal separator, `.` or `,`.
+    pub decimal: Option<char>,
+    pub delimiter: Option<char>,
+    /// how many lines to skip before the header.
+    pub skip: Option<usize>,
+    /// positive amounts are outcome, like in credit card exports.
+    #[serde(default)]
+    pub negate: bool,
+    pub account: Option<String>,
+    pub category: Option<String>,
+}
 
-/// Read the config file and return the database config.
+/// The migrations shipped with rustance.
+/// They are written into the migrate dir on startup if missing,
+/// so existing wallets pick up new ones automatically.
+const MIGRATIONS: &[(&str, &str)] = &[
+    (
+        "20250311140451_init.sql",
+        include_str!("../database/migrations/20250311140451_init.sql"),
+    ),
+    (
+        "20251018100000_occurred_at.sql",
+        include_str!("../database/migrations/20251018100000_occurred_at.sql"),
+    ),
+    (
+        "20251018110000_account.sql",
+        include_str!("../database/migrations/20251018110000_account.sql"),
+    ),
+    (
+        "20251018120000_transfer.sql",
+        include_str!("../database/migrations/20251018120000_transfer.sql"),
+    ),
+    (
+        "20251018130000_category.sql",
+        include_str!("../database/migrations/20251018130000_category.sql"),
+    ),
+    (
+        "20251018140000_tag.sql",
+        include_str!("../database/migrations/20251018140000_tag.sql"),
+    ),
+    (
+        "20251018150000_budget.sql",
+        include_str!("../database/migrations/20251018150000_budget.sql"),
+    ),
+    (
+        "20251018160000_recurring.sql",
+        include_str!("../database/migrations/20251018160000_recurring.sql"),
+    ),
+    (
+        "20251018170000_external_id.sql",
+        include_str!("../database/migrations/20251018170000_external_id.sql"),
+    ),
+    (
+        "20251018180000_external_id_unique.sql",
+        include_str!("../database/migrations/20251018180000_external_id_unique.sql"),
+    ),
+    (
+        "20251019090000_operation.sql",
+        include_str!("../database/migrations/20251019090000_operation.sql"),
+    ),
+    (
+        "20251019100000_trash.sql",
+        include_str!("../database/migrations/20251019100000_trash.sql"),
+    ),
+    (
+        "20251019110000_operation_author.sql",
+        include_str!("../database/migrations/20251019110000_operation_author.sql"),
+    ),
+    (
+        "20251019120000_ledger.sql",
+        include_str!("../database/migrations/20251019120000_ledger.sql"),
+    ),
+    (
+        "20251019130000_record_search.sql",
+        include_str!("../database/migrations/20251019130000_record_search.sql"),
+    ),
+    (
+        "20251019140000_budget_month.sql",
+        include_str!("../database/migrations/20251019140000_budget_month.sql"),
+    ),
+    (
+        "20251019150000_operation_row_discarded.sql",
+        include_str!("../database/migrations/20251019150000_operation_row_discarded.sql"),
+    ),
+];
+
+/// Read the config file and return the config.
 /// if not exist, create it.
-pub fn read_config() -> Result<DatabaseConfig, Box<dyn std::error::Error>> {
+pub fn read_config() -> Result<Config, Box<dyn std::error::Error>> {
     let home_dir = home_dir().expect("Failed to get home directory");
     let amount_path = home_dir.join(".rustance/database/");
     if !amount_path.exists() {
@@ -37,9 +155,10 @@ pub fn read_config() -> Result<DatabaseConfig, Box<dyn std::error::Error>> {
         fs::write(
             &config_path,
             format!(
-                "[database_config]\ndatabase = \"{}/wallet.db\"\nmigrates = \"{}/migrates\"",
+                "[database_config]\ndatabase = \"{}/wallet.db\"\nmigrates = \"{}/migrates\"\n\n[time_config]\ntimezone = \"{}\"\n",
+                &amount_path.to_string_lossy(),
                 &amount_path.to_string_lossy(),
-                &amount_path.to_string_lossy()
+                DEFAULT_TIMEZONE
             ),
         )
         .expect("write config error");
@@ -48,30 +167,35 @@ pub fn read_config() -> Result<DatabaseConfig, Box<dyn std::error::Error>> {
     let migrate_path = amount_path.join("migrates/");
     if !&migrate_path.exists() {
         fs::create_dir_all(&migrate_path).expect("connot create dir");
-        fs::File::create(migrate_path.join("20250311140451_init.sql"))
-            .expect("create migrates error");
-        fs::write(
-            migrate_path.join("20250311140451_init.sql"),
-            "create table if not exists amount_record (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    amount INTEGER not null default 0,
-    in_or_out BOOLEAN not null,
-    append_msg TEXT not null,
-    created_at DATETIME not null default CURRENT_TIMESTAMP,
-    updated_at DATETIME not null default CURRENT_TIMESTAMP
-);",
-        )
-        .expect("write config error");
+    }
+    for (name, sql) in MIGRATIONS {
+        let file = migrate_path.join(name);
+        if !file.exists() {
+            fs::write(file, sql).expect("write migrates error");
+        }
     }
 
     let toml_content = fs::read_to_string(&config_path)?;
     let config: Config = toml::from_str(&toml_content)?;
-    Ok(config.database_config)
+    Ok(config)
+}
+
+/// Set the timezone from `--tz`, or the config file if not overridden.
+pub fn init_timezone(overridden: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let config = read_config()?;
+    let name = overridden
+        .map(str::to_string)
+        .or(config.time_config.timezone)
+        .unwrap_or(DEFAULT_TIMEZONE.to_string());
+    set_timezone(parse_timezone(&name)?);
+    Ok(())
 }
 
 /// Initialize the database.
 pub async fn init_check_database_all() -> PathBuf {
-    let config = read_config().expect("unable to read config");
+    let config = read_config()
+        .expect("unable to read config")
+        .database_config;
     let wallet_db = &config.database;
     let migrate_path = &config.migrates;
 
@@ -87,18 +211,18 @@ async fn create_database(database_name: &str) -> Result<(), Box<dyn Error>> {
         .await
         .unwrap_or(false)
     {
-        println!(
+        print_notice(format!(
             "{} {}",
             "Creating database".green().bold(),
             database_name.blue().bold()
-        );
+        ));
         match Sqlite::create_database(database_name).await {
-            Ok(_) => println!(
+            Ok(_) => print_notice(format!(
                 "{}{} {}",
                 "Create db:".green().bold(),
                 database_name.blue().bold(),
                 "success!".green().bold()
-            ),
+            )),
             Err(error) => panic!("{}{}", "error: ".red().bold(), error),
         }
     }
@@ -120,3 +244,23 @@ async fn check_database(database_name: &str, migrate_path: &str) -> Result<(), B
 
     Ok(())
 }
+
+/// a new wallet with all the migrations in the temp dir, for the tests.
+#[cfg(test)]
+pub async fn test_database(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rustance-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let migrate_path = dir.join("migrates");
+    fs::create_dir_all(&migrate_path).unwrap();
+    for (name, sql) in MIGRATIONS {
+        fs::write(migrate_path.join(name), sql).unwrap();
+    }
+
+    let database = dir.join("wallet.db");
+    let database_name = database.to_string_lossy();
+    create_database(&database_name).await.unwrap();
+    check_database(&database_name, &migrate_path.to_string_lossy())
+        .await
+        .unwrap();
+    database
+}
@@ -0,0 +1,314 @@
+use crate::cli_customs::DedupeArgs;
+use crate::import::ImportRecord;
+use crate::journal::{journal, record_images};
+use crate::money::Money;
+use crate::output::{ask, print_notice, print_rows, print_table};
+use crate::process::{AMOUNT_SELECT, Amount};
+use crate::time_utils::to_local;
+use chrono::{Duration, Utc};
+use colored::*;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use std::collections::HashSet;
+use std::error::Error;
+use std::path::PathBuf;
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Panel,
+        object::Rows,
+        style::{Border, BorderColor, Style},
+        themes::Colorization,
+    },
+};
+
+/// how similar two messages must be for the records to be duplicates,
+/// from 0 for nothing in common to 1 for the same.
+const SIMILARITY: f64 = 0.5;
+
+/// two records which are suspected to be the same.
+#[derive(FromRow, Clone, Debug, Serialize)]
+struct DuplicatePair {
+    first: i64,
+    second: i64,
+    first_msg: String,
+    second_msg: String,
+}
+
+/// a row of the duplicate review output.
+#[derive(Clone, Debug, Tabled)]
+struct DuplicateRow {
+    choice: String,
+    id: i32,
+    amount: Money,
+    in_or_out: bool,
+    append_msg: String,
+    account: String,
+    category: String,
+    tags: String,
+    occurred_at: String,
+}
+
+/// the adjacent character pairs of the letters and digits of a message.
+fn bigrams(msg: &str) -> Vec<(char, char)> {
+    let chars: Vec<char> = msg
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect();
+    chars.windows(2).map(|x| (x[0], x[1])).collect()
+}
+
+/// whether two messages are similar enough, by the dice coefficient of their bigrams.
+/// two empty messages are similar.
+pub fn similar(a: &str, b: &str) -> bool {
+    let (a, b) = (bigrams(a), bigrams(b));
+    if a.is_empty() && b.is_empty() {
+        return true;
+    }
+    let mut rest = b.clone();
+    let mut common = 0;
+    for x in &a {
+        if let Some(i) = rest.iter().position(|y| y == x) {
+            rest.swap_remove(i);
+            common += 1;
+        }
+    }
+    (2 * common) as f64 / (a.len() + b.len()) as f64 >= SIMILARITY
+}
+
+/// the id of an existing record `record` is suspected to duplicate.
+/// transfers are never duplicates.
+pub async fn find_duplicate(
+    pool: &SqlitePool,
+    record: &ImportRecord,
+    within_days: i64,
+) -> Result<Option<i64>, Box<dyn Error>> {
+    if record.transfer {
+        return Ok(None);
+    }
+    let query = r#"
+        SELECT
+            id, append_msg
+        FROM
+            amount_record
+        WHERE
+            amount = ? AND in_or_out = ? AND transfer_id IS NULL AND deleted_at IS NULL
+            AND occurred_at >= ? AND occurred_at <= ?
+        ORDER BY
+            id
+    "#;
+    let candidates: Vec<(i64, String)> = sqlx::query_as(query)
+        .bind(record.amount)
+        .bind(record.in_or_out)
+        .bind(
+            (record.occurred_at - Duration::days(within_days))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+        )
+        .bind(
+            (record.occurred_at + Duration::days(within_days))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+        )
+        .fetch_all(pool)
+        .await?;
+    Ok(candidates
+        .into_iter()
+        .find(|(_, msg)| similar(msg, &record.append_msg))
+        .map(|x| x.0))
+}
+
+/// dispatch the `dedupe` command.
+/// every suspected pair is shown to keep one of them, the other is merged into it.
+pub async fn dedupe_command(
+    args: &DedupeArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    // records with their own ids from the same source are different trades.
+    let query = r#"
+        SELECT
+            a.id AS first, b.id AS second, a.append_msg AS first_msg, b.append_msg AS second_msg
+        FROM
+            amount_record AS a
+            JOIN amount_record AS b
+                ON a.id < b.id AND a.amount = b.amount AND a.in_or_out = b.in_or_out
+        WHERE
+            a.transfer_id IS NULL AND b.transfer_id IS NULL
+            AND a.deleted_at IS NULL AND b.deleted_at IS NULL
+            AND abs(julianday(a.occurred_at) - julianday(b.occurred_at)) <= ?
+            AND NOT (a.source IS b.source AND a.external_id IS NOT NULL AND b.external_id IS NOT NULL)
+        ORDER BY
+            a.occurred_at, a.id, b.id
+    "#;
+    let pairs: Vec<DuplicatePair> = sqlx::query_as::<_, DuplicatePair>(query)
+        .bind(args.within_days)
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .filter(|x| similar(&x.first_msg, &x.second_msg))
+        .collect();
+    // the machine output lists the pairs, merging them needs the questions.
+    if print_rows(&pairs)? {
+        return Ok(());
+    }
+    if pairs.is_empty() {
+        print_notice("No suspected duplicate.".yellow().bold());
+        return Ok(());
+    }
+
+    let mut merged: HashSet<i64> = HashSet::new();
+    for (i, pair) in pairs.iter().enumerate() {
+        if merged.contains(&pair.first) || merged.contains(&pair.second) {
+            continue;
+        }
+        let records: Vec<Amount> = sqlx::query_as(&format!(
+            "{} WHERE amount_record.id IN (?, ?) ORDER BY amount_record.id",
+            AMOUNT_SELECT
+        ))
+        .bind(pair.first)
+        .bind(pair.second)
+        .fetch_all(&pool)
+        .await?;
+
+        let rows: Vec<DuplicateRow> = records
+            .iter()
+            .zip(["1", "2"])
+            .map(|(x, choice)| DuplicateRow {
+                choice: choice.to_string(),
+                id: x.id,
+                amount: x.amount,
+                in_or_out: x.in_or_out,
+                append_msg: x.append_msg.clone(),
+                account: x.account.clone().unwrap_or_default(),
+                category: x.category.clone().unwrap_or_default(),
+                tags: x.tags.clone().unwrap_or_default(),
+                occurred_at: to_local(x.occurred_at)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+            })
+            .collect();
+        let table = Table::new(rows)
+            .with(Style::blank())
+            .with(Colorization::columns([
+                Color::FG_BRIGHT_YELLOW,
+                Color::FG_BRIGHT_WHITE,
+                Color::FG_BRIGHT_BLUE,
+                Color::FG_BRIGHT_RED,
+                Color::FG_BRIGHT_CYAN,
+                Color::FG_BRIGHT_WHITE,
+                Color::FG_BRIGHT_CYAN,
+                Color::FG_BRIGHT_GREEN,
+                Color::FG_MAGENTA,
+            ]))
+            .with(Panel::header(format!(
+                "Suspected Duplicate {} / {}\n",
+                i + 1,
+                pairs.len()
+            )))
+            .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+            .modify(Rows::first(), Alignment::center())
+            .with(
+                Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)),
+            )
+            .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+            .to_string();
+        print_table(table);
+
+        let input = ask(
+            "Input 1 or 2 to keep that record and merge the other into it, s to skip, q to quit."
+                .blue()
+                .bold(),
+            "the duplicates can only be reviewed in a terminal",
+        )?;
+        let (keep, drop) = match input.as_str() {
+            "1" => (pair.first, pair.second),
+            "2" => (pair.second, pair.first),
+            "q" => break,
+            _ => {
+                print_notice("Skipped".yellow().bold());
+                continue;
+            }
+        };
+
+        merge_records(&pool, keep, drop).await?;
+        merged.insert(drop);
+        print_notice(format!(
+            "{}{}{}{}",
+            "Merged ".yellow().bold(),
+            format!("#{}", drop).purple().bold(),
+            " into ".yellow().bold(),
+            format!("#{}", keep).purple().bold()
+        ));
+    }
+
+    Ok(())
+}
+
+/// merge the record `drop` into `keep` and move it to the trash.
+/// `keep` gets the tags of `drop`, and its category and external id if it has none.
+async fn merge_records(pool: &SqlitePool, keep: i64, drop: i64) -> Result<(), Box<dyn Error>> {
+    let mut tx = pool.begin().await?;
+    // `drop` comes first, so an undo gives the external id back to it after `keep` lets it go.
+    let before = record_images(&mut tx, &[drop, keep]).await?;
+    let (category_id, source, external_id) =
+        sqlx::query_as::<_, (Option<i64>, Option<String>, Option<String>)>(
+            "SELECT category_id, source, external_id FROM amount_record WHERE id = ?",
+        )
+        .bind(drop)
+        .fetch_one(&mut *tx)
+        .await?;
+    let keep_external_id: Option<String> =
+        sqlx::query_scalar("SELECT external_id FROM amount_record WHERE id = ?")
+            .bind(keep)
+            .fetch_one(&mut *tx)
+            .await?;
+    // the external id is unique, it moves to `keep` if `keep` has none.
+    let (source, external_id) = match keep_external_id {
+        Some(_) => (None, None),
+        None => (source, external_id),
+    };
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO record_tag (record_id, tag_id) SELECT ?, tag_id FROM record_tag WHERE record_id = ?",
+    )
+    .bind(keep)
+    .bind(drop)
+    .execute(&mut *tx)
+    .await?;
+    let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    sqlx::query(
+        "UPDATE amount_record SET deleted_at = ?, external_id = CASE WHEN ? IS NULL THEN external_id END WHERE id = ?",
+    )
+    .bind(&now)
+    .bind(&external_id)
+    .bind(drop)
+    .execute(&mut *tx)
+    .await?;
+
+    let query = r#"
+        UPDATE amount_record
+        SET
+            category_id = ifnull(category_id, ?),
+            source = CASE WHEN external_id IS NULL AND ? IS NOT NULL THEN ? ELSE source END,
+            external_id = ifnull(external_id, ?),
+            updated_at = ?
+        WHERE id = ?
+    "#;
+    sqlx::query(query)
+        .bind(category_id)
+        .bind(&external_id)
+        .bind(source)
+        .bind(&external_id)
+        .bind(&now)
+        .bind(keep)
+        .execute(&mut *tx)
+        .await?;
+    journal(&mut tx, "dedupe", before).await?;
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use crate::cli_customs::{ExportArgs, ExportFormat, OutputFormat};
+use crate::output::{output_format, print_notice};
+use crate::process::{Amount, select_records};
+use crate::time_utils::to_local;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// a record as it is exported, all times are local.
+/// `rustance import rustance` reads it back.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ExportRecord {
+    pub id: i32,
+    pub occurred_at: String,
+    pub amount: String,
+    pub in_or_out: bool,
+    /// the amount with its direction, income is positive.
+    pub signed_amount: String,
+    pub append_msg: String,
+    pub account: Option<String>,
+    pub category: Option<String>,
+    /// separated by spaces.
+    pub tags: Option<String>,
+    /// both sides of a transfer share it.
+    pub transfer_id: Option<i64>,
+    /// the account on the other side of a transfer.
+    pub transfer_account: Option<String>,
+    pub source: Option<String>,
+    pub external_id: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// the format of an export file, from its extension.
+pub fn format_of(file: &str) -> ExportFormat {
+    match file.rsplit('.').next().map(str::to_lowercase).as_deref() {
+        Some("json") => ExportFormat::Json,
+        Some("ndjson") | Some("jsonl") => ExportFormat::Ndjson,
+        _ => ExportFormat::Csv,
+    }
+}
+
+/// export the records matching the filters.
+pub async fn export_command(
+    args: &ExportArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    let records = select_records(&pool, &args.filter).await?;
+    let exported = export_records(&pool, &records).await?;
+
+    let mut writer: Box<dyn Write> = match args.file {
+        Some(ref file) => Box::new(io::BufWriter::new(
+            fs::File::create(file).map_err(|e| format!("unable to write `{}`: {}", file, e))?,
+        )),
+        None => Box::new(io::stdout().lock()),
+    };
+    let format = args.format.unwrap_or(match output_format() {
+        OutputFormat::Json => ExportFormat::Json,
+        _ => ExportFormat::Csv,
+    });
+    match format {
+        ExportFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(&mut writer);
+            for x in &exported {
+                csv_writer.serialize(x)?;
+            }
+            csv_writer.flush()?;
+        }
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, &exported)?;
+            writeln!(writer)?;
+        }
+        ExportFormat::Ndjson => {
+            for x in &exported {
+                serde_json::to_writer(&mut writer, x)?;
+                writeln!(writer)?;
+            }
+        }
+    }
+    writer.flush()?;
+
+    if let Some(ref file) = args.file {
+        print_notice(format!(
+            "{}{}",
+            format!("Exported {} records to ", exported.len())
+                .yellow()
+                .bold(),
+            file.purple().bold()
+        ));
+    }
+    Ok(())
+}
+
+/// the records as they are exported.
+pub async fn export_records(
+    pool: &SqlitePool,
+    records: &[Amount],
+) -> Result<Vec<ExportRecord>, Box<dyn Error>> {
+    let transfer_accounts = transfer_accounts(pool).await?;
+    Ok(records
+        .iter()
+        .map(|x| export_record(x, &transfer_accounts))
+        .collect())
+}
+
+/// the record ids and accounts of both sides of every transfer.
+async fn transfer_accounts(
+    pool: &SqlitePool,
+) -> Result<HashMap<i64, Vec<(i32, String)>>, Box<dyn Error>> {
+    let query = r#"
+        SELECT
+            amount_record.transfer_id, amount_record.id, account.name
+        FROM
+            amount_record
+            JOIN account ON account.id = amount_record.account_id
+        WHERE
+            amount_record.transfer_id IS NOT NULL
+    "#;
+    let mut sides: HashMap<i64, Vec<(i32, String)>> = HashMap::new();
+    for (transfer_id, id, account) in sqlx::query_as::<_, (i64, i32, String)>(query)
+        .fetch_all(pool)
+        .await?
+    {
+        sides.entry(transfer_id).or_default().push((id, account));
+    }
+    Ok(sides)
+}
+
+fn export_record(x: &Amount, transfer_accounts: &HashMap<i64, Vec<(i32, String)>>) -> ExportRecord {
+    let local = |time| to_local(time).format("%Y-%m-%d %H:%M:%S").to_string();
+    ExportRecord {
+        id: x.id,
+        occurred_at: local(x.occurred_at),
+        amount: x.amount.to_string(),
+        in_or_out: x.in_or_out,
+        signed_amount: x.signed_amount().to_string(),
+        append_msg: x.append_msg.clone(),
+        account: x.account.clone(),
+        category: x.category.clone(),
+        tags: x.tags.clone(),
+        transfer_id: x.transfer_id,
+        transfer_account: x
+            .transfer_id
+            .and_then(|id| transfer_accounts.get(&id))
+            .and_then(|sides| sides.iter().find(|(id, _)| *id != x.id))
+            .map(|(_, account)| account.clone()),
+        source: x.source.clone(),
+        external_id: x.external_id.clone(),
+        created_at: local(x.created_at),
+        updated_at: local(x.updated_at),
+    }
+}
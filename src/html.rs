@@ -0,0 +1,353 @@
+use crate::budget::month_budget_status;
+use crate::category::category_subtotals;
+use crate::chart::{CategorySpend, category_spends, chart_months, day_spends};
+use crate::cli_customs::ReportHtmlArgs;
+use crate::money::Money;
+use crate::output::print_notice;
+use crate::process::{Amount, group_amount_by_month, select_range_records};
+use crate::report::PeriodSummary;
+use crate::time_utils::{DateRange, local_today, to_local};
+use chrono::{Datelike, Utc};
+use colored::*;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+
+/// the colors of the chart series and the donut slices.
+const PALETTE: [&str; 8] = [
+    "#e4572e", "#17bebb", "#ffc914", "#76b041", "#7d5ba6", "#3f88c5", "#f49d37", "#8c8c8c",
+];
+const INCOME_COLOR: &str = "#2e9e5b";
+const OUTCOME_COLOR: &str = "#d64545";
+
+/// how many of the largest expenses are listed.
+const TOP_EXPENSES: usize = 10;
+
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", "PingFang SC", sans-serif; margin: 2rem auto; max-width: 960px; color: #222; padding: 0 1rem; }
+h1 { margin-bottom: 0.2rem; }
+h2 { border-bottom: 2px solid #ffc914; padding-bottom: 0.2rem; margin-top: 2.5rem; }
+.muted { color: #888; font-size: 0.9rem; }
+.cards { display: flex; flex-wrap: wrap; gap: 1rem; margin: 1.5rem 0; }
+.card { flex: 1 1 140px; border: 1px solid #ddd; border-radius: 8px; padding: 0.8rem 1rem; }
+.card .label { color: #888; font-size: 0.85rem; }
+.card .value { font-size: 1.4rem; font-weight: 600; margin-top: 0.2rem; }
+.income { color: #2e9e5b; }
+.outcome { color: #d64545; }
+table { border-collapse: collapse; width: 100%; margin: 0.8rem 0; font-size: 0.9rem; }
+th, td { text-align: left; padding: 0.35rem 0.5rem; border-bottom: 1px solid #eee; }
+th { background: #fafafa; }
+td.amount { text-align: right; font-variant-numeric: tabular-nums; }
+tr.total td { font-weight: 600; border-top: 2px solid #ccc; }
+.donut { display: flex; align-items: center; gap: 2rem; flex-wrap: wrap; }
+.legend span { display: inline-block; width: 0.8rem; height: 0.8rem; border-radius: 2px; margin-right: 0.4rem; vertical-align: middle; }
+svg text { font-size: 10px; fill: #666; }
+"#;
+
+/// escape the text to put into html.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// the class of an amount, income or outcome.
+fn sign_class(positive: bool) -> &'static str {
+    match positive {
+        true => "income",
+        false => "outcome",
+    }
+}
+
+/// an svg chart of grouped bars, one group for every label
+/// and a bar in every group for every series.
+fn bar_svg(labels: &[String], series: &[(&str, Vec<i64>)]) -> String {
+    let (width, height, left, bottom, top) = (760.0, 260.0, 70.0, 30.0, 10.0);
+    let max = series
+        .iter()
+        .flat_map(|x| x.1.iter())
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let plot_height = height - bottom - top;
+    let group_width = (width - left) / labels.len().max(1) as f64;
+    let bar_width = group_width * 0.8 / series.len().max(1) as f64;
+    // a label under every group would overlap for the days of a month.
+    let label_step = labels.len().div_ceil(16).max(1);
+
+    let mut svg = format!(
+        r#"<svg viewBox="0 0 {} {}" width="100%" role="img">"#,
+        width, height
+    );
+    for i in 0..=4 {
+        let value = max * i as f64 / 4.0;
+        let y = top + plot_height - plot_height * i as f64 / 4.0;
+        let _ = write!(
+            svg,
+            r##"<line x1="{left}" y1="{y:.1}" x2="{width}" y2="{y:.1}" stroke="#eee"/><text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"##,
+            left - 6.0,
+            y + 3.0,
+            Money::from_minor(value as i64)
+        );
+    }
+    for (i, label) in labels.iter().enumerate() {
+        let x = left + group_width * i as f64 + group_width * 0.1;
+        for (j, (color, values)) in series.iter().enumerate() {
+            let bar_height = plot_height * values[i].max(0) as f64 / max;
+            let _ = write!(
+                svg,
+                r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}"><title>{} {}</title></rect>"#,
+                x + bar_width * j as f64,
+                top + plot_height - bar_height,
+                bar_width,
+                bar_height,
+                color,
+                escape(label),
+                Money::from_minor(values[i])
+            );
+        }
+        if i % label_step == 0 {
+            let _ = write!(
+                svg,
+                r#"<text x="{:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+                x + group_width * 0.4,
+                height - bottom + 15.0,
+                escape(label)
+            );
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// an svg donut of the outcome of the categories with its legend,
+/// the categories after the largest ones are put together.
+fn donut_html(spends: &[CategorySpend]) -> String {
+    let total: i64 = spends.iter().map(|x| x.outcome.minor()).sum();
+    if total <= 0 {
+        return r#"<p class="muted">No outcome in the period.</p>"#.to_string();
+    }
+    let mut slices: Vec<(String, i64)> = spends
+        .iter()
+        .take(PALETTE.len() - 1)
+        .map(|x| (x.category.clone(), x.outcome.minor()))
+        .collect();
+    let others: i64 = spends
+        .iter()
+        .skip(PALETTE.len() - 1)
+        .map(|x| x.outcome.minor())
+        .sum();
+    if others > 0 {
+        slices.push(("(others)".to_string(), others));
+    }
+
+    // a circle of radius 15.915 is 100 long, so the dashes are in percent.
+    let mut svg = String::from(r#"<svg viewBox="0 0 42 42" width="220" height="220" role="img">"#);
+    let mut legend = String::from(r#"<div class="legend">"#);
+    let mut offset = 25.0;
+    for ((category, outcome), color) in slices.iter().zip(PALETTE) {
+        let percent = *outcome as f64 * 100.0 / total as f64;
+        let _ = write!(
+            svg,
+            r#"<circle cx="21" cy="21" r="15.915" fill="none" stroke="{}" stroke-width="6" stroke-dasharray="{:.3} {:.3}" stroke-dashoffset="{:.3}"><title>{} {:.1}%</title></circle>"#,
+            color,
+            percent,
+            100.0 - percent,
+            offset,
+            escape(category),
+            percent
+        );
+        offset -= percent;
+        let _ = write!(
+            legend,
+            r#"<div><span style="background:{}"></span>{} <b>{}</b> <span class="muted">({:.1}%)</span></div>"#,
+            color,
+            escape(category),
+            Money::from_minor(*outcome),
+            percent
+        );
+    }
+    svg.push_str("</svg>");
+    legend.push_str("</div>");
+    format!(r#"<div class="donut">{}{}</div>"#, svg, legend)
+}
+
+/// a table of records.
+fn records_table(records: &[&Amount]) -> String {
+    let mut html = String::from(
+        "<table><tr><th>id</th><th>occurred_at</th><th>amount</th><th>message</th><th>account</th><th>category</th><th>tags</th></tr>",
+    );
+    for x in records {
+        let _ = write!(
+            html,
+            r#"<tr><td>{}</td><td>{}</td><td class="amount {}">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>"#,
+            x.id,
+            to_local(x.occurred_at).format("%Y-%m-%d %H:%M"),
+            match x.transfer_id {
+                Some(_) => "",
+                None => sign_class(x.in_or_out),
+            },
+            x.signed_amount(),
+            escape(&x.append_msg),
+            escape(x.account.as_deref().unwrap_or_default()),
+            escape(x.category.as_deref().unwrap_or_default()),
+            escape(x.tags.as_deref().unwrap_or_default())
+        );
+    }
+    html.push_str("</table>");
+    html
+}
+
+/// a card of the summary.
+fn card(label: &str, value: impl std::fmt::Display, class: &str) -> String {
+    format!(
+        r#"<div class="card"><div class="label">{}</div><div class="value {}">{}</div></div>"#,
+        label, class, value
+    )
+}
+
+/// write a static html report of the period, everything inline to open offline.
+pub async fn html_report(
+    args: &ReportHtmlArgs,
+    pool: &sqlx::SqlitePool,
+) -> Result<(), Box<dyn Error>> {
+    let mut range = args.range.range()?;
+    if range.is_all() {
+        // this month by default.
+        range = DateRange::month(local_today())?;
+    }
+    let records = select_range_records(pool, &range).await?;
+    let total = PeriodSummary::of(range.to_string(), &records)?;
+
+    let mut body = String::new();
+    let _ = write!(
+        body,
+        r#"<h1>Rustance Report</h1><div class="muted">{} · generated at {}</div><div class="cards">{}{}{}{}{}</div>"#,
+        range,
+        to_local(Utc::now()).format("%Y-%m-%d %H:%M"),
+        card("Income", total.income, "income"),
+        card("Outcome", total.outcome, "outcome"),
+        card("Net", total.net, sign_class(!total.net.is_negative())),
+        card("Savings Rate", &total.savings_rate, ""),
+        card("Records", total.records, "")
+    );
+
+    // the trend by month, or by day for a single month.
+    let months = chart_months(&range, &records);
+    let (title, labels, income, outcome) = match months.len() > 1 {
+        true => {
+            let summaries: Vec<PeriodSummary> = months
+                .iter()
+                .map(|first| {
+                    PeriodSummary::of(
+                        first.format("%Y-%m").to_string(),
+                        records.iter().filter(|x| {
+                            let day = to_local(x.occurred_at).date_naive();
+                            day.year() == first.year() && day.month() == first.month()
+                        }),
+                    )
+                })
+                .collect::<Result<_, _>>()?;
+            (
+                "Trend By Month",
+                summaries.iter().map(|x| x.period.clone()).collect(),
+                summaries.iter().map(|x| x.income.minor()).collect(),
+                summaries.iter().map(|x| x.outcome.minor()).collect(),
+            )
+        }
+        false => {
+            let days = day_spends(&months, &records)?;
+            (
+                "Trend By Day",
+                days.iter()
+                    .map(|x| x.date[8..].to_string())
+                    .collect::<Vec<String>>(),
+                days.iter().map(|x| x.income.minor()).collect(),
+                days.iter().map(|x| x.outcome.minor()).collect(),
+            )
+        }
+    };
+    let _ = write!(
+        body,
+        r#"<h2>{}</h2><div class="muted"><span class="income">■ income</span> <span class="outcome">■ outcome</span></div>{}"#,
+        title,
+        bar_svg(&labels, &[(INCOME_COLOR, income), (OUTCOME_COLOR, outcome)])
+    );
+
+    let _ = write!(
+        body,
+        "<h2>Outcome By Category</h2>{}",
+        donut_html(&category_spends(&records)?)
+    );
+
+    let mut expenses: Vec<&Amount> = records
+        .iter()
+        .filter(|x| x.transfer_id.is_none() && !x.in_or_out)
+        .collect();
+    expenses.sort_by_key(|x| std::cmp::Reverse(x.amount.minor()));
+    expenses.truncate(TOP_EXPENSES);
+    let _ = write!(body, "<h2>Top Expenses</h2>{}", records_table(&expenses));
+
+    // the same tables as `list-all`, a month each.
+    let grouped = group_amount_by_month(&records);
+    let mut sorted_months: Vec<String> = grouped.keys().cloned().collect();
+    sorted_months.sort_unstable();
+    for month in sorted_months {
+        let month_records: Vec<&Amount> = grouped[&month].iter().collect();
+        let _ = write!(
+            body,
+            "<h2>Accounts Of the Month {}</h2>{}",
+            month,
+            records_table(&month_records)
+        );
+
+        let month_total = Money::total(grouped[&month].iter().map(Amount::statistic_amount))?;
+        let mut statistics = format!(
+            r#"<table><tr><th>statistics</th><th>category</th><th>amount</th></tr><tr class="total"><td>Month Total</td><td></td><td class="amount {}">{}</td></tr>"#,
+            sign_class(!month_total.is_negative()),
+            month_total
+        );
+        for (path, subtotal) in category_subtotals(&grouped[&month])? {
+            let _ = write!(
+                statistics,
+                r#"<tr><td>Category Total</td><td>{}</td><td class="amount {}">{}</td></tr>"#,
+                escape(&path),
+                sign_class(!subtotal.is_negative()),
+                subtotal
+            );
+        }
+        for x in month_budget_status(pool, &month).await? {
+            let _ = write!(
+                statistics,
+                r#"<tr><td>Budget Left ({} / {})</td><td>{}</td><td class="amount {}">{}</td></tr>"#,
+                x.spent,
+                x.budget,
+                escape(&x.label()),
+                sign_class(!x.remaining().is_negative()),
+                x.remaining()
+            );
+        }
+        statistics.push_str("</table>");
+        body.push_str(&statistics);
+    }
+
+    let html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\"><head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width, initial-scale=1\"><title>Rustance Report {}</title><style>{}</style></head><body>{}</body></html>\n",
+        range, STYLE, body
+    );
+    let file = match args.file {
+        Some(ref file) => file.clone(),
+        None => "rustance-report.html".to_string(),
+    };
+    fs::write(&file, html).map_err(|e| format!("unable to write `{}`: {}", file, e))?;
+
+    print_notice(format!(
+        "{}{}",
+        "Wrote the report to ".green().bold(),
+        file.purple().bold()
+    ));
+    Ok(())
+}
@@ -0,0 +1,925 @@
+use crate::account::{find_account, resolve_account};
+use crate::bill::{decode_bill, read_bill};
+use crate::category::resolve_category;
+use crate::cli_customs::{
+    ExportFormat, ImportArgs, ImportBillArgs, ImportCommands, ImportCsvArgs, ImportRustanceArgs,
+};
+use crate::database::{CsvProfile, read_config};
+use crate::dedupe::find_duplicate;
+use crate::export::{ExportRecord, format_of};
+use crate::journal::{inserted, journal};
+use crate::money::Money;
+use crate::output::{confirm, print_notice, print_rows, print_table};
+use crate::tag::{add_record_tags, normalize_tag, parse_tags};
+use crate::time_utils::{local_midnight, local_to_utc, to_local};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use colored::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Panel,
+        object::Rows,
+        style::{Border, BorderColor, Style},
+        themes::Colorization,
+    },
+};
+
+/// the date formats tried when the profile does not set one.
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%Y/%m/%d %H:%M",
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%Y.%m.%d",
+    "%Y%m%d",
+    "%d.%m.%Y",
+];
+
+/// a record read from an import file, not inserted yet.
+#[derive(Clone, Debug, Default)]
+pub struct ImportRecord {
+    /// the line in the file, for the preview and errors.
+    pub line: usize,
+    pub amount: Money,
+    pub in_or_out: bool,
+    pub append_msg: String,
+    pub occurred_at: DateTime<Utc>,
+    pub tags: Vec<String>,
+    /// a transfer between the account and the transfer account,
+    /// `in_or_out` is the side of the account.
+    pub transfer: bool,
+    /// the id of the record in the source, e.g. a trade number.
+    pub external_id: Option<String>,
+    /// the record's own account, category and source,
+    /// the ones of the `ImportTarget` are used if not set.
+    pub account_id: Option<i64>,
+    pub transfer_account_id: Option<i64>,
+    pub category_id: Option<i64>,
+    pub source: Option<String>,
+}
+
+/// where the imported records go.
+pub struct ImportTarget {
+    /// where the records come from, e.g. `csv:<profile>` or `alipay`.
+    /// the external ids are unique in a source.
+    pub source: Option<String>,
+    pub account_id: Option<i64>,
+    /// the other account of the transfers.
+    pub transfer_account_id: Option<i64>,
+    pub category_id: Option<i64>,
+}
+
+/// how to import the records, from the command line.
+pub struct ImportOptions {
+    pub yes: bool,
+    pub dry_run: bool,
+    pub skip_duplicates: bool,
+    pub within_days: i64,
+}
+
+/// a row of the import preview.
+#[derive(Clone, Debug, Tabled, Serialize)]
+struct ImportRow {
+    line: usize,
+    amount: Money,
+    in_or_out: bool,
+    transfer: bool,
+    append_msg: String,
+    tags: String,
+    occurred_at: String,
+    /// the existing record it is suspected to duplicate.
+    duplicate: String,
+}
+
+/// dispatch the `import` subcommands.
+pub async fn import_command(
+    args: &ImportArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    match &args.command {
+        ImportCommands::Csv(arg) => import_csv(arg, &pool).await,
+        ImportCommands::Alipay(arg) => import_bill(arg, &pool, "alipay").await,
+        ImportCommands::Wechat(arg) => import_bill(arg, &pool, "wechat").await,
+        ImportCommands::Rustance(arg) => import_rustance(arg, &pool).await,
+    }
+}
+
+/// the profile of `--profile` with the command line options on top.
+fn csv_profile(args: &ImportCsvArgs) -> Result<CsvProfile, Box<dyn Error>> {
+    let mut profile = match args.profile {
+        Some(ref name) => read_config()?.import_profiles.remove(name).ok_or(format!(
+            "import profile `{}` does not exist in the config file",
+            name
+        ))?,
+        None => CsvProfile::default(),
+    };
+
+    let overridden = [
+        (&mut profile.amount, &args.amount),
+        (&mut profile.debit, &args.debit),
+        (&mut profile.credit, &args.credit),
+        (&mut profile.date, &args.date),
+        (&mut profile.id, &args.id),
+        (&mut profile.date_format, &args.date_format),
+        (&mut profile.account, &args.account),
+        (&mut profile.category, &args.category),
+    ];
+    for (field, arg) in overridden {
+        if arg.is_some() {
+            field.clone_from(arg);
+        }
+    }
+    if !args.msg.is_empty() {
+        profile.msg = args.msg.clone();
+    }
+    profile.decimal = args.decimal.or(profile.decimal);
+    profile.delimiter = args.delimiter.or(profile.delimiter);
+    profile.skip = args.skip.or(profile.skip);
+    profile.negate |= args.negate;
+
+    if profile.amount.is_some() && (profile.debit.is_some() || profile.credit.is_some()) {
+        return Err("use either an amount column or debit/credit columns, not both".into());
+    }
+    if profile.amount.is_none() && (profile.debit.is_none() || profile.credit.is_none()) {
+        return Err("map an amount column, or both the debit and credit columns".into());
+    }
+    if profile.date.is_none() {
+        return Err("map the date column".into());
+    }
+    if !matches!(profile.decimal, None | Some('.') | Some(',')) {
+        return Err("the decimal separator must be `.` or `,`".into());
+    }
+    Ok(profile)
+}
+
+/// find a column by its header, or by its 1-based position.
+fn find_column(headers: &[String], column: &str) -> Result<usize, String> {
+    let column = column.trim();
+    if let Some(i) = headers.iter().position(|x| x == column) {
+        return Ok(i);
+    }
+    if let Some(i) = headers
+        .iter()
+        .position(|x| x.to_lowercase() == column.to_lowercase())
+    {
+        return Ok(i);
+    }
+    match column.parse::<usize>() {
+        Ok(i) if i >= 1 && i <= headers.len() => Ok(i - 1),
+        _ => Err(format!(
+            "column `{}` is not in the header: {}",
+            column,
+            headers.join(", ")
+        )),
+    }
+}
+
+/// the currency symbols allowed around an amount.
+const CURRENCY_SYMBOLS: &[char] = &[
+    '$', '¥', '￥', '€', '£', '₩', '₹', '₽', '₺', '₫', '฿', '₴', '₪',
+];
+
+/// parse an amount like `-1.234,56`, `(12.00)` or `¥ 12.50` into a signed money.
+/// besides the digits and the `decimal` separator, only the other of `.` and `,`
+/// before it as the thousands separator, a sign, currency symbols and spaces are allowed.
+/// an empty cell is `None`.
+pub fn parse_decimal(raw: &str, decimal: char) -> Result<Option<Money>, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    let thousands = if decimal == ',' { '.' } else { ',' };
+    let (body, parenthesized) = match raw.strip_prefix('(').and_then(|x| x.strip_suffix(')')) {
+        Some(body) => (body, true),
+        None => (raw, false),
+    };
+
+    let (mut normalized, mut sign, mut seen_decimal) = (String::new(), None, false);
+    for c in body.chars() {
+        match c {
+            '0'..='9' => normalized.push(c),
+            c if c == decimal && seen_decimal => {
+                return Err(format!(
+                    "invalid amount `{}`, more than one decimal separator `{}`",
+                    raw, decimal
+                ));
+            }
+            c if c == decimal => {
+                seen_decimal = true;
+                normalized.push('.');
+            }
+            c if c == thousands && !seen_decimal && !normalized.is_empty() => {}
+            '-' | '+' if sign.is_none() && !parenthesized && normalized.is_empty() => {
+                sign = Some(c)
+            }
+            c if c.is_whitespace() || CURRENCY_SYMBOLS.contains(&c) => {}
+            c => return Err(format!("invalid amount `{}`, unexpected `{}`", raw, c)),
+        }
+    }
+    if parenthesized || sign == Some('-') {
+        normalized.insert(0, '-');
+    }
+    normalized
+        .parse::<Money>()
+        .map(Some)
+        .map_err(|_| format!("invalid amount `{}`", raw))
+}
+
+/// parse a date of an import file, a bare date is the local midnight.
+pub fn parse_import_date(raw: &str, format: Option<&str>) -> Result<DateTime<Utc>, String> {
+    let raw = raw.trim();
+    let formats = match format {
+        Some(format) => vec![format],
+        None => DATE_FORMATS.to_vec(),
+    };
+    for format in formats {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(raw, format) {
+            return Ok(local_to_utc(datetime));
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(raw, format) {
+            return Ok(local_midnight(date));
+        }
+    }
+    Err(format!("invalid date `{}`", raw))
+}
+
+/// read the records of a csv file with `profile`.
+/// returns the records and how many rows are skipped for having no amount.
+fn read_csv(
+    content: &str,
+    profile: &CsvProfile,
+    tags: &[String],
+) -> Result<(Vec<ImportRecord>, usize), Box<dyn Error>> {
+    let skip = profile.skip.unwrap_or(0);
+    let body: String = content
+        .trim_start_matches('\u{feff}')
+        .split_inclusive('\n')
+        .skip(skip)
+        .collect();
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(profile.delimiter.unwrap_or(',') as u8)
+        .flexible(true)
+        .has_headers(false)
+        .from_reader(body.as_bytes());
+    let mut rows = reader.records();
+
+    let headers: Vec<String> = match rows.next() {
+        Some(header) => header?.iter().map(|x| x.trim().to_string()).collect(),
+        None => return Err("the csv file is empty".into()),
+    };
+    let column = |name: &Option<String>| -> Result<Option<usize>, String> {
+        name.as_deref()
+            .map(|x| find_column(&headers, x))
+            .transpose()
+    };
+    let amount = column(&profile.amount)?;
+    let debit = column(&profile.debit)?;
+    let credit = column(&profile.credit)?;
+    let date = column(&profile.date)?.unwrap();
+    let id = column(&profile.id)?;
+    let msg = profile
+        .msg
+        .iter()
+        .map(|x| find_column(&headers, x))
+        .collect::<Result<Vec<usize>, String>>()?;
+    let decimal = profile.decimal.unwrap_or('.');
+
+    let mut records = Vec::new();
+    let mut skipped = 0;
+    for row in rows {
+        let row = row?;
+        let line = row.position().map_or(0, |x| x.line() as usize) + skip;
+        if row.iter().all(|x| x.trim().is_empty()) {
+            continue;
+        }
+        let cell = |i: usize| row.get(i).unwrap_or("");
+        let at_line = |e: String| format!("line {}: {}", line, e);
+
+        let signed = match amount {
+            Some(i) => parse_decimal(cell(i), decimal)
+                .map_err(at_line)?
+                .map(|x| if profile.negate { -x } else { x }),
+            None => {
+                let debit = parse_decimal(cell(debit.unwrap()), decimal).map_err(at_line)?;
+                let credit = parse_decimal(cell(credit.unwrap()), decimal).map_err(at_line)?;
+                match (debit, credit) {
+                    (Some(debit), Some(credit))
+                        if debit != Money::ZERO && credit != Money::ZERO =>
+                    {
+                        return Err(at_line("both debit and credit are set".to_string()).into());
+                    }
+                    (Some(debit), _) if debit != Money::ZERO => Some(-debit.abs()),
+                    (_, Some(credit)) => Some(credit.abs()),
+                    (debit, None) => debit,
+                }
+            }
+        };
+        let signed = match signed {
+            Some(x) if x != Money::ZERO => x,
+            _ => {
+                skipped += 1;
+                continue;
+            }
+        };
+
+        let append_msg = msg
+            .iter()
+            .map(|i| cell(*i).trim())
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" ");
+        records.push(ImportRecord {
+            line,
+            amount: signed.abs(),
+            in_or_out: signed.is_positive(),
+            occurred_at: parse_import_date(cell(date), profile.date_format.as_deref())
+                .map_err(at_line)?,
+            tags: parse_tags(Some(&append_msg), tags).map_err(at_line)?,
+            append_msg,
+            external_id: id
+                .map(|i| cell(i).trim().to_string())
+                .filter(|x| !x.is_empty()),
+            ..Default::default()
+        });
+    }
+    Ok((records, skipped))
+}
+
+/// the name of an imported file without its directories, e.g. `bank.csv`.
+fn csv_file_name(file: &str) -> String {
+    Path::new(file)
+        .file_name()
+        .map_or(file.to_string(), |x| x.to_string_lossy().to_string())
+}
+
+async fn import_csv(args: &ImportCsvArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let profile = csv_profile(args)?;
+    let content = fs::read_to_string(&args.file)
+        .map_err(|e| format!("unable to read `{}`: {}", args.file, e))?;
+    let (records, skipped) = read_csv(&content, &profile, &args.tags)?;
+
+    // the ids are only unique in the files of a profile,
+    // without one they are kept apart by the file name.
+    let target = ImportTarget {
+        source: Some(match args.profile {
+            Some(ref profile) => format!("csv:{}", profile),
+            None => format!("csv-file:{}", csv_file_name(&args.file)),
+        }),
+        account_id: Some(resolve_account(pool, profile.account.as_deref()).await?),
+        transfer_account_id: None,
+        category_id: match profile.category {
+            Some(ref category) => Some(resolve_category(pool, category).await?),
+            None => None,
+        },
+    };
+
+    import_records(
+        pool,
+        records,
+        &target,
+        &format!("Import Preview Of {}\n", args.file),
+        &ImportOptions {
+            yes: args.yes,
+            dry_run: args.dry_run,
+            skip_duplicates: args.skip_duplicates,
+            within_days: args.within_days,
+        },
+    )
+    .await?;
+    if skipped > 0 {
+        print_notice(format!(
+            "{}{}",
+            "Skipped rows without an amount: ".yellow().bold(),
+            skipped.to_string().purple().bold()
+        ));
+    }
+    Ok(())
+}
+
+async fn import_bill(
+    args: &ImportBillArgs,
+    pool: &SqlitePool,
+    source: &'static str,
+) -> Result<(), Box<dyn Error>> {
+    let content = decode_bill(
+        &fs::read(&args.file).map_err(|e| format!("unable to read `{}`: {}", args.file, e))?,
+    );
+    let (records, skipped) = read_bill(&content, &args.tags)?;
+
+    let target = ImportTarget {
+        source: Some(source.to_string()),
+        account_id: Some(resolve_account(pool, args.account.as_deref()).await?),
+        transfer_account_id: match args.transfer_account {
+            Some(ref account) => Some(resolve_account(pool, Some(account)).await?),
+            None => None,
+        },
+        category_id: match args.category {
+            Some(ref category) => Some(resolve_category(pool, category).await?),
+            None => None,
+        },
+    };
+    if target.transfer_account_id == target.account_id {
+        return Err("the transfer account must differ from the account".into());
+    }
+
+    import_records(
+        pool,
+        records,
+        &target,
+        &format!("Import Preview Of {}\n", args.file),
+        &ImportOptions {
+            yes: args.yes,
+            dry_run: args.dry_run,
+            skip_duplicates: args.skip_duplicates,
+            within_days: args.within_days,
+        },
+    )
+    .await?;
+    if skipped > 0 {
+        print_notice(format!(
+            "{}{}",
+            "Skipped closed, failed or refunded trades: "
+                .yellow()
+                .bold(),
+            skipped.to_string().purple().bold()
+        ));
+    }
+    Ok(())
+}
+
+/// read the records of a file written by `rustance export`.
+fn read_export(content: &str, format: ExportFormat) -> Result<Vec<ExportRecord>, Box<dyn Error>> {
+    Ok(match format {
+        ExportFormat::Csv => csv::Reader::from_reader(content.as_bytes())
+            .deserialize()
+            .collect::<Result<Vec<ExportRecord>, csv::Error>>()?,
+        ExportFormat::Json => serde_json::from_str(content)?,
+        ExportFormat::Ndjson => content
+            .lines()
+            .filter(|x| !x.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<ExportRecord>, serde_json::Error>>()?,
+    })
+}
+
+/// the key of an exported record, from its id and content.
+/// it stays the same when the record is exported again unchanged.
+fn export_key(x: &ExportRecord) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        &x.id.to_string(),
+        &x.occurred_at,
+        &x.signed_amount,
+        &x.append_msg,
+        x.account.as_deref().unwrap_or(""),
+        &x.created_at,
+    ] {
+        // the length keeps the parts apart.
+        hasher.update(part.len().to_string());
+        hasher.update(":");
+        hasher.update(part);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// import the records exported by rustance, keeping their accounts, categories,
+/// tags, transfers and sources.
+async fn import_rustance(
+    args: &ImportRustanceArgs,
+    pool: &SqlitePool,
+) -> Result<(), Box<dyn Error>> {
+    let content = fs::read_to_string(&args.file)
+        .map_err(|e| format!("unable to read `{}`: {}", args.file, e))?;
+    let exported = read_export(&content, args.format.unwrap_or(format_of(&args.file)))?;
+
+    // a transfer is imported once, from the side keeping the external id if any.
+    let mut transfer_sides: HashMap<i64, usize> = HashMap::new();
+    for (i, x) in exported.iter().enumerate() {
+        if let Some(transfer_id) = x.transfer_id {
+            let side = transfer_sides.entry(transfer_id).or_insert(i);
+            if exported[*side].external_id.is_none() && x.external_id.is_some() {
+                *side = i;
+            }
+        }
+    }
+
+    let mut accounts: HashMap<String, i64> = HashMap::new();
+    let mut categories: HashMap<String, i64> = HashMap::new();
+    let mut records = Vec::new();
+    for (i, x) in exported.iter().enumerate() {
+        if x.transfer_id.is_some_and(|id| transfer_sides[&id] != i) {
+            continue;
+        }
+        let at_record = |e: String| format!("record {}: {}", i + 1, e);
+
+        let mut account_ids = Vec::new();
+        for name in [&x.account, &x.transfer_account].into_iter().flatten() {
+            let id = match accounts.get(name) {
+                Some(id) => *id,
+                None => {
+                    let id = find_account(pool, name)
+                        .await?
+                        .ok_or(format!(
+                            "account `{}` does not exist, add it with `rustance account add`",
+                            name
+                        ))
+                        .map_err(at_record)?
+                        .0;
+                    accounts.insert(name.clone(), id);
+                    id
+                }
+            };
+            account_ids.push(id);
+        }
+        let category_id = match x.category {
+            Some(ref path) => Some(match categories.get(path) {
+                Some(id) => *id,
+                None => {
+                    let id = resolve_category(pool, path)
+                        .await
+                        .map_err(|e| at_record(e.to_string()))?;
+                    categories.insert(path.clone(), id);
+                    id
+                }
+            }),
+            None => None,
+        };
+        if x.transfer_id.is_some() && x.transfer_account.is_none() {
+            return Err(at_record("the transfer has no transfer_account".to_string()).into());
+        }
+
+        // a record without an external id is known by its key,
+        // so importing the same file again skips it.
+        let (source, external_id) = match x.external_id {
+            Some(ref id) => (x.source.clone(), id.clone()),
+            None => (
+                Some(x.source.clone().unwrap_or("rustance".to_string())),
+                export_key(x),
+            ),
+        };
+        let amount: Money = x.amount.parse().map_err(at_record)?;
+        records.push(ImportRecord {
+            line: i + 1,
+            amount: amount.abs(),
+            in_or_out: x.in_or_out,
+            append_msg: x.append_msg.clone(),
+            occurred_at: parse_import_date(&x.occurred_at, Some("%Y-%m-%d %H:%M:%S"))
+                .map_err(at_record)?,
+            tags: x
+                .tags
+                .as_deref()
+                .unwrap_or("")
+                .split_whitespace()
+                .map(normalize_tag)
+                .collect::<Result<Vec<String>, String>>()
+                .map_err(at_record)?,
+            transfer: x.transfer_id.is_some(),
+            external_id: Some(external_id),
+            account_id: account_ids.first().copied(),
+            transfer_account_id: account_ids.get(1).copied(),
+            category_id,
+            source,
+        });
+    }
+
+    import_records(
+        pool,
+        records,
+        &ImportTarget {
+            source: None,
+            account_id: Some(resolve_account(pool, None).await?),
+            transfer_account_id: None,
+            category_id: None,
+        },
+        &format!("Import Preview Of {}\n", args.file),
+        &ImportOptions {
+            yes: args.yes,
+            dry_run: args.dry_run,
+            skip_duplicates: args.skip_duplicates,
+            within_days: args.within_days,
+        },
+    )
+    .await
+}
+
+/// preview `records`, then insert them in one transaction after confirming.
+/// the records whose external id is already imported from the source are left out.
+pub async fn import_records(
+    pool: &SqlitePool,
+    records: Vec<ImportRecord>,
+    target: &ImportTarget,
+    header: &str,
+    options: &ImportOptions,
+) -> Result<(), Box<dyn Error>> {
+    let imported: HashSet<(Option<String>, String)> = sqlx::query_as(
+        "SELECT source, external_id FROM amount_record WHERE external_id IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+    let mut seen = HashSet::new();
+    let total = records.len();
+    let records: Vec<ImportRecord> = records
+        .into_iter()
+        .map(|mut x| {
+            x.account_id = x.account_id.or(target.account_id);
+            x.transfer_account_id = x.transfer_account_id.or(target.transfer_account_id);
+            x.category_id = x.category_id.or(target.category_id);
+            x.source = x.source.or(target.source.clone());
+            x
+        })
+        .filter(|x| match x.external_id {
+            Some(ref id) => {
+                let key = (x.source.clone(), id.clone());
+                !imported.contains(&key) && seen.insert(key)
+            }
+            None => true,
+        })
+        .collect();
+    if records.len() < total {
+        print_notice(format!(
+            "{}{}",
+            "Skipped records already imported: ".yellow().bold(),
+            (total - records.len()).to_string().purple().bold()
+        ));
+    }
+
+    let mut duplicates = Vec::with_capacity(records.len());
+    for x in &records {
+        duplicates.push(find_duplicate(pool, x, options.within_days).await?);
+    }
+    let suspected = duplicates.iter().flatten().count();
+    let (records, duplicates): (Vec<ImportRecord>, Vec<Option<i64>>) = records
+        .into_iter()
+        .zip(duplicates)
+        .filter(|(_, duplicate)| !options.skip_duplicates || duplicate.is_none())
+        .unzip();
+    if options.skip_duplicates && suspected > 0 {
+        print_notice(format!(
+            "{}{}",
+            "Skipped suspected duplicates: ".yellow().bold(),
+            suspected.to_string().purple().bold()
+        ));
+    }
+
+    if records.is_empty() {
+        print_notice("No record to import.".yellow().bold());
+        return Ok(());
+    }
+    let transfers = records.iter().filter(|x| x.transfer).count();
+    let missing = records
+        .iter()
+        .filter(|x| x.transfer && x.transfer_account_id.is_none())
+        .count();
+    if missing > 0 {
+        return Err(format!(
+            "{} trades are transfers (不计收支), set the other account with `--transfer-account`",
+            missing
+        )
+        .into());
+    }
+
+    let rows: Vec<ImportRow> = records
+        .iter()
+        .zip(&duplicates)
+        .map(|(x, duplicate)| ImportRow {
+            line: x.line,
+            amount: x.amount,
+            in_or_out: x.in_or_out,
+            transfer: x.transfer,
+            append_msg: x.append_msg.clone(),
+            tags: x.tags.join(" "),
+            occurred_at: to_local(x.occurred_at)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            duplicate: duplicate.map_or(String::new(), |id| format!("#{}", id)),
+        })
+        .collect();
+    if !print_rows(&rows)? {
+        let table = Table::new(rows)
+            .with(Style::blank())
+            .with(Colorization::columns([
+                Color::FG_BRIGHT_WHITE,
+                Color::FG_BRIGHT_BLUE,
+                Color::FG_BRIGHT_RED,
+                Color::FG_BRIGHT_RED,
+                Color::FG_BRIGHT_CYAN,
+                Color::FG_BRIGHT_GREEN,
+                Color::FG_MAGENTA,
+                Color::FG_BRIGHT_YELLOW,
+            ]))
+            .with(Panel::header(header))
+            .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+            .modify(Rows::first(), Alignment::center())
+            .with(
+                Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)),
+            )
+            .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+            .to_string();
+        print_table(table);
+    }
+
+    let statistic = |in_or_out: bool| {
+        Money::total(
+            records
+                .iter()
+                .filter(|x| !x.transfer && x.in_or_out == in_or_out)
+                .map(|x| x.amount),
+        )
+    };
+    print_notice(format!(
+        "{}{}{}{}{}{}{}{}",
+        "Records: ".yellow().bold(),
+        records.len().to_string().purple().bold(),
+        ", income: ".yellow().bold(),
+        statistic(true)?.to_string().green().bold(),
+        ", outcome: ".yellow().bold(),
+        statistic(false)?.to_string().red().bold(),
+        ", transfers: ".yellow().bold(),
+        transfers.to_string().purple().bold()
+    ));
+    if suspected > 0 && !options.skip_duplicates {
+        print_notice(format!(
+            "{}{}{}",
+            "Warning: ".red().bold(),
+            format!("{} records look like existing ones, ", suspected)
+                .yellow()
+                .bold(),
+            "leave them out with `--skip-duplicates` or merge them later with `rustance dedupe`."
+                .yellow()
+                .bold()
+        ));
+    }
+    if options.dry_run {
+        return Ok(());
+    }
+
+    if !options.yes
+        && !confirm(
+            "Input Yes(YES/yes/Y/y) to import, other to give up."
+                .blue()
+                .bold(),
+        )?
+    {
+        print_notice("Give up".red().bold());
+        return Ok(());
+    }
+
+    let query = r#"
+        INSERT INTO amount_record (amount, in_or_out, append_msg, account_id, category_id, transfer_id, source, external_id, occurred_at, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    "#;
+    let mut tx = pool.begin().await?;
+    let mut record_ids = Vec::new();
+    for x in &records {
+        let transfer_id = if x.transfer {
+            Some(
+                sqlx::query("INSERT INTO transfer (created_at) VALUES (?)")
+                    .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+                    .execute(&mut *tx)
+                    .await?
+                    .last_insert_rowid(),
+            )
+        } else {
+            None
+        };
+
+        // the other side of a transfer goes into the transfer account,
+        // only the side in the account keeps the external id.
+        let account_id = x
+            .account_id
+            .ok_or("the account of the records is not set")?;
+        let mut sides = vec![(account_id, x.in_or_out, x.external_id.as_deref())];
+        if let Some(other) = x.transfer_account_id.filter(|_| x.transfer) {
+            sides.push((other, !x.in_or_out, None));
+        }
+        for (account_id, in_or_out, external_id) in sides {
+            let record_id = sqlx::query(query)
+                .bind(x.amount)
+                .bind(in_or_out)
+                .bind(&x.append_msg)
+                .bind(account_id)
+                .bind(if x.transfer { None } else { x.category_id })
+                .bind(transfer_id)
+                .bind(&x.source)
+                .bind(external_id)
+                .bind(x.occurred_at.format("%Y-%m-%d %H:%M:%S").to_string())
+                .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+                .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+            add_record_tags(&mut tx, record_id, &x.tags).await?;
+            record_ids.push(record_id);
+        }
+    }
+    let command = match target.source {
+        Some(ref source) => format!("import {}", source),
+        None => "import rustance".to_string(),
+    };
+    journal(&mut tx, &command, inserted(&record_ids)).await?;
+    tx.commit().await?;
+
+    print_notice(format!(
+        "{}{}",
+        "Imported records: ".green().bold(),
+        records.len().to_string().purple().bold()
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_customs::{Cli, Commands};
+    use crate::database::test_database;
+    use crate::export::export_command;
+    use crate::process::insert_in_or_out_come;
+    use clap::Parser;
+
+    fn parse(args: &[&str]) -> Commands {
+        Cli::try_parse_from([&["rustance"], args].concat())
+            .unwrap()
+            .command
+    }
+
+    async fn record_count(database: &PathBuf) -> i64 {
+        let pool = SqlitePool::connect_with(SqliteConnectOptions::new().filename(database))
+            .await
+            .unwrap();
+        sqlx::query_scalar("SELECT count(*) FROM amount_record")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn importing_an_export_twice_adds_the_records_once() {
+        let from = test_database("reimport-from").await;
+        for msg in ["lunch", "dinner"] {
+            let Commands::Outcome(args) = parse(&["outcome", "12.50", msg]) else {
+                unreachable!()
+            };
+            insert_in_or_out_come(&args, from.clone(), false)
+                .await
+                .unwrap();
+        }
+        let file = from.with_file_name("export.csv");
+        let file = file.to_string_lossy();
+        let Commands::Export(args) = parse(&["export", "-f", &file]) else {
+            unreachable!()
+        };
+        export_command(&args, from.clone()).await.unwrap();
+
+        let to = test_database("reimport-to").await;
+        let Commands::Import(args) = parse(&["import", "rustance", &file, "--yes"]) else {
+            unreachable!()
+        };
+        import_command(&args, to.clone()).await.unwrap();
+        assert_eq!(record_count(&to).await, 2);
+        import_command(&args, to.clone()).await.unwrap();
+        assert_eq!(record_count(&to).await, 2);
+    }
+
+    #[tokio::test]
+    async fn csv_ids_of_different_files_do_not_collide() {
+        let database = test_database("csv-source").await;
+        let import = |name: &str, content: &str| {
+            let file = database.with_file_name(name);
+            fs::write(&file, content).unwrap();
+            let file = file.to_string_lossy().to_string();
+            let Commands::Import(args) = parse(&[
+                "import", "csv", &file, "--amount", "amount", "--date", "date", "--id", "id",
+                "--msg", "msg", "--yes",
+            ]) else {
+                unreachable!()
+            };
+            args
+        };
+        let bank = import(
+            "bank.csv",
+            "id,date,amount,msg\n1,2025-03-01,-12.50,lunch\n",
+        );
+        let card = import(
+            "card.csv",
+            "id,date,amount,msg\n1,2025-03-20,-99.00,shoes\n",
+        );
+        for args in [&bank, &card, &bank] {
+            import_command(args, database.clone()).await.unwrap();
+        }
+        assert_eq!(record_count(&database).await, 2);
+    }
+}
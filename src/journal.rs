@@ -0,0 +1,542 @@
+use crate::cli_customs::{HistoryArgs, RedoArgs, UndoArgs};
+use crate::ledger::{append_chain, chain_image};
+use crate::output::{print_notice, print_rows, print_table};
+use crate::time_utils::to_local;
+use chrono::Utc;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use sqlx::prelude::FromRow;
+use sqlx::{SqliteConnection, SqlitePool, sqlite::SqliteConnectOptions};
+use std::error::Error;
+use std::path::PathBuf;
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Panel, Width,
+        object::{Columns, Rows},
+        style::{Border, BorderColor, Style},
+        themes::Colorization,
+    },
+};
+
+/// a row of `amount_record` with its tags, as the journal keeps it.
+#[derive(FromRow, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RecordImage {
+    pub id: i64,
+    pub amount: i64,
+    pub in_or_out: bool,
+    pub append_msg: String,
+    pub account_id: Option<i64>,
+    pub transfer_id: Option<i64>,
+    pub category_id: Option<i64>,
+    pub source: Option<String>,
+    pub external_id: Option<String>,
+    pub occurred_at: String,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub deleted_at: Option<String>,
+    /// the ids of the tags.
+    #[sqlx(skip)]
+    pub tags: Vec<i64>,
+}
+
+/// the images of records before a change, `None` for the records it inserts.
+pub type Before = Vec<(i64, Option<RecordImage>)>;
+
+/// a change of a record by an operation.
+#[derive(Clone, Debug)]
+pub struct Revision {
+    pub operation_id: i64,
+    pub command: String,
+    pub author: Option<String>,
+    pub undone: bool,
+    pub discarded: bool,
+    /// in utc.
+    pub created_at: String,
+    pub before: Option<RecordImage>,
+    pub after: Option<RecordImage>,
+}
+
+/// an operation of the journal.
+#[derive(FromRow, Clone, Debug)]
+struct Operation {
+    id: i64,
+    command: String,
+}
+
+/// a row of the `history` output.
+#[derive(FromRow, Clone, Debug, Tabled, Serialize)]
+struct HistoryRow {
+    id: i64,
+    command: String,
+    /// the ids of the changed records.
+    records: String,
+    /// `undone` for the operations which can be redone,
+    /// `discarded` for the ones which can not be undone or redone any more.
+    state: String,
+    author: String,
+    created_at: String,
+}
+
+/// the image of the record with `id`, `None` if it does not exist.
+pub async fn record_image(
+    conn: &mut SqliteConnection,
+    id: i64,
+) -> Result<Option<RecordImage>, Box<dyn Error>> {
+    let query = r#"
+        SELECT
+            id, amount, in_or_out, append_msg, account_id, transfer_id, category_id,
+            source, external_id, occurred_at, created_at, updated_at, deleted_at
+        FROM
+            amount_record
+        WHERE
+            id = ?
+    "#;
+    let mut image: Option<RecordImage> = sqlx::query_as(query)
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if let Some(ref mut image) = image {
+        image.tags =
+            sqlx::query_scalar("SELECT tag_id FROM record_tag WHERE record_id = ? ORDER BY tag_id")
+                .bind(id)
+                .fetch_all(&mut *conn)
+                .await?;
+    }
+    Ok(image)
+}
+
+/// the images of the records with `ids` before they are patched or deleted.
+pub async fn record_images(
+    conn: &mut SqliteConnection,
+    ids: &[i64],
+) -> Result<Before, Box<dyn Error>> {
+    let mut before = Vec::new();
+    for id in ids {
+        before.push((*id, record_image(conn, *id).await?));
+    }
+    Ok(before)
+}
+
+/// who runs the command, the login name of the system.
+fn author() -> Option<String> {
+    ["USER", "USERNAME"]
+        .iter()
+        .find_map(|x| std::env::var(x).ok())
+        .filter(|x| !x.is_empty())
+}
+
+/// the `Before` of the records with `ids` which are inserted.
+pub fn inserted(ids: &[i64]) -> Before {
+    ids.iter().map(|x| (*x, None)).collect()
+}
+
+/// write the change of the records in `before` by `command` into the journal,
+/// in the transaction of the change, after it is done.
+/// the undone operations are discarded, they can not be redone any more
+/// but stay in the history of their records.
+/// the change goes into the hash chain too, if it is enabled.
+pub async fn journal(
+    conn: &mut SqliteConnection,
+    command: &str,
+    before: Before,
+) -> Result<(), Box<dyn Error>> {
+    if before.is_empty() {
+        return Ok(());
+    }
+    sqlx::query("UPDATE operation SET discarded = 1 WHERE undone = 1")
+        .execute(&mut *conn)
+        .await?;
+    let operation_id = sqlx::query(
+        "INSERT INTO operation (command, author, undone, created_at) VALUES (?, ?, 0, ?)",
+    )
+    .bind(command)
+    .bind(author())
+    .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    let query = r#"
+        INSERT INTO operation_row (operation_id, record_id, before, after)
+        VALUES (?, ?, ?, ?)
+    "#;
+    let mut changes = Vec::new();
+    for (id, image) in before {
+        let after = record_image(conn, id).await?;
+        let (image, after) = (chain_image(image.as_ref())?, chain_image(after.as_ref())?);
+        sqlx::query(query)
+            .bind(operation_id)
+            .bind(id)
+            .bind(&image)
+            .bind(&after)
+            .execute(&mut *conn)
+            .await?;
+        changes.push((id, image, after));
+    }
+    append_chain(conn, command, &changes).await
+}
+
+/// the changes of the record with `id`, the earliest first.
+pub async fn record_revisions(pool: &SqlitePool, id: i64) -> Result<Vec<Revision>, Box<dyn Error>> {
+    let query = r#"
+        SELECT
+            operation.id, operation.command, operation.author, operation.undone,
+            operation.discarded OR operation_row.discarded, operation.created_at,
+            operation_row.before, operation_row.after
+        FROM
+            operation_row
+            JOIN operation ON operation.id = operation_row.operation_id
+        WHERE
+            operation_row.record_id = ?
+        ORDER BY
+            operation.id, operation_row.id
+    "#;
+    type Row = (
+        i64,
+        String,
+        Option<String>,
+        bool,
+        bool,
+        String,
+        Option<String>,
+        Option<String>,
+    );
+    let mut revisions = Vec::new();
+    for (operation_id, command, author, undone, discarded, created_at, before, after) in
+        sqlx::query_as::<_, Row>(query)
+            .bind(id)
+            .fetch_all(pool)
+            .await?
+    {
+        revisions.push(Revision {
+            operation_id,
+            command,
+            author,
+            undone,
+            discarded,
+            created_at,
+            before: before.map(|x| serde_json::from_str(&x)).transpose()?,
+            after: after.map(|x| serde_json::from_str(&x)).transpose()?,
+        });
+    }
+    Ok(revisions)
+}
+
+/// put the record with `id` back from the image `from` to `to`.
+/// fails if the record is not `from` any more, e.g. it is changed by another command.
+async fn restore_record(
+    conn: &mut SqliteConnection,
+    id: i64,
+    from: Option<RecordImage>,
+    to: Option<RecordImage>,
+) -> Result<(), Box<dyn Error>> {
+    let current = record_image(conn, id).await?;
+    if current.is_none() && from.is_some() {
+        return Err(format!("record #{} is purged, can not restore it", id).into());
+    }
+    if current != from {
+        return Err(format!("record #{} is changed since, can not restore it", id).into());
+    }
+
+    sqlx::query("DELETE FROM record_tag WHERE record_id = ?")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    let Some(to) = to else {
+        sqlx::query("DELETE FROM amount_record WHERE id = ?")
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+        return Ok(());
+    };
+
+    if let Some(transfer_id) = to.transfer_id {
+        sqlx::query("INSERT OR IGNORE INTO transfer (id, created_at) VALUES (?, ?)")
+            .bind(transfer_id)
+            .bind(&to.created_at)
+            .execute(&mut *conn)
+            .await?;
+    }
+    let query = match current {
+        Some(_) => {
+            r#"
+            UPDATE amount_record
+            SET amount = ?, in_or_out = ?, append_msg = ?, account_id = ?, transfer_id = ?, category_id = ?,
+                source = ?, external_id = ?, occurred_at = ?, created_at = ?, updated_at = ?, deleted_at = ?
+            WHERE id = ?
+            "#
+        }
+        None => {
+            r#"
+            INSERT INTO amount_record (amount, in_or_out, append_msg, account_id, transfer_id, category_id,
+                source, external_id, occurred_at, created_at, updated_at, deleted_at, id)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#
+        }
+    };
+    sqlx::query(query)
+        .bind(to.amount)
+        .bind(to.in_or_out)
+        .bind(&to.append_msg)
+        .bind(to.account_id)
+        .bind(to.transfer_id)
+        .bind(to.category_id)
+        .bind(&to.source)
+        .bind(&to.external_id)
+        .bind(&to.occurred_at)
+        .bind(&to.created_at)
+        .bind(&to.updated_at)
+        .bind(&to.deleted_at)
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    // the tags removed since are left out.
+    for tag_id in &to.tags {
+        sqlx::query(
+            "INSERT INTO record_tag (record_id, tag_id) SELECT ?, id FROM tag WHERE id = ?",
+        )
+        .bind(id)
+        .bind(tag_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// undo or redo the operation, restoring its records from `before` to `after` or back.
+async fn replay_operation(
+    conn: &mut SqliteConnection,
+    operation: &Operation,
+    undo: bool,
+) -> Result<(), Box<dyn Error>> {
+    let rows: Vec<(i64, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT record_id, before, after FROM operation_row WHERE operation_id = ? AND discarded = 0 ORDER BY id",
+    )
+    .bind(operation.id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let image = |x: Option<String>| -> Result<Option<RecordImage>, serde_json::Error> {
+        x.map(|x| serde_json::from_str(&x)).transpose()
+    };
+    let mut steps = Vec::new();
+    for (id, before, after) in rows {
+        let (before, after) = (image(before)?, image(after)?);
+        steps.push(match undo {
+            true => (id, after, before),
+            false => (id, before, after),
+        });
+    }
+    if undo {
+        steps.reverse();
+    }
+    let mut changes = Vec::new();
+    for (id, from, to) in steps {
+        let before = chain_image(from.as_ref())?;
+        restore_record(conn, id, from, to).await.map_err(|e| {
+            format!(
+                "can not {} operation #{} ({}): {}",
+                if undo { "undo" } else { "redo" },
+                operation.id,
+                operation.command,
+                e
+            )
+        })?;
+        let after = chain_image(record_image(conn, id).await?.as_ref())?;
+        changes.push((id, before, after));
+    }
+    let command = format!("{} #{}", if undo { "undo" } else { "redo" }, operation.id);
+    append_chain(conn, &command, &changes).await?;
+
+    sqlx::query("DELETE FROM transfer WHERE id NOT IN (SELECT transfer_id FROM amount_record WHERE transfer_id IS NOT NULL)")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query("UPDATE operation SET undone = ? WHERE id = ?")
+        .bind(undo)
+        .bind(operation.id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// undo the last `count` operations, from the latest.
+pub async fn undo_command(args: &UndoArgs, database_path: PathBuf) -> Result<(), Box<dyn Error>> {
+    replay_command(args.count, true, database_path).await
+}
+
+/// redo the last `count` undone operations, from the earliest.
+pub async fn redo_command(args: &RedoArgs, database_path: PathBuf) -> Result<(), Box<dyn Error>> {
+    replay_command(args.count, false, database_path).await
+}
+
+async fn replay_command(
+    count: u32,
+    undo: bool,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    let query = match undo {
+        true => {
+            "SELECT id, command FROM operation WHERE undone = 0 AND discarded = 0 ORDER BY id DESC LIMIT ?"
+        }
+        false => {
+            "SELECT id, command FROM operation WHERE undone = 1 AND discarded = 0 ORDER BY id LIMIT ?"
+        }
+    };
+    let operations: Vec<Operation> = sqlx::query_as(query).bind(count).fetch_all(&pool).await?;
+    if operations.is_empty() {
+        print_notice(
+            format!("Nothing to {}.", if undo { "undo" } else { "redo" })
+                .yellow()
+                .bold(),
+        );
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+    for operation in &operations {
+        replay_operation(&mut tx, operation, undo).await?;
+    }
+    tx.commit().await?;
+
+    for operation in &operations {
+        print_notice(format!(
+            "{}{}{}",
+            if undo { "Undone " } else { "Redone " }.yellow().bold(),
+            format!("#{} ", operation.id).purple().bold(),
+            operation.command.purple().bold()
+        ));
+    }
+    Ok(())
+}
+
+/// list the recent operations, the latest first.
+pub async fn history_command(
+    args: &HistoryArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    let rows = history_rows(&pool, args.limit).await?;
+    if print_rows(&rows)? {
+        return Ok(());
+    }
+    if rows.is_empty() {
+        print_notice("No change yet.".yellow().bold());
+        return Ok(());
+    }
+
+    let table = Table::new(rows)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_BRIGHT_BLUE,
+            Color::FG_BRIGHT_RED,
+            Color::FG_BRIGHT_GREEN,
+            Color::FG_MAGENTA,
+        ]))
+        .with(Panel::header("History\n"))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .modify(Columns::single(2), Width::wrap(40))
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .to_string();
+
+    print_table(table);
+
+    Ok(())
+}
+
+/// the `limit` recent operations, the latest first.
+async fn history_rows(pool: &SqlitePool, limit: u32) -> Result<Vec<HistoryRow>, Box<dyn Error>> {
+    let query = r#"
+        SELECT
+            operation.id, operation.command,
+            (
+                SELECT group_concat(record_id, ' ') FROM (
+                    SELECT record_id FROM operation_row
+                    WHERE operation_row.operation_id = operation.id ORDER BY record_id
+                )
+            ) AS records,
+            CASE
+                WHEN operation.discarded THEN 'discarded'
+                WHEN operation.undone THEN 'undone'
+                ELSE ''
+            END AS state,
+            ifnull(operation.author, '') AS author,
+            operation.created_at
+        FROM
+            operation
+        ORDER BY
+            operation.id DESC
+        LIMIT ?
+    "#;
+    let mut rows: Vec<HistoryRow> = sqlx::query_as(query).bind(limit).fetch_all(pool).await?;
+    for x in &mut rows {
+        if let Ok(time) = chrono::NaiveDateTime::parse_from_str(&x.created_at, "%Y-%m-%d %H:%M:%S")
+        {
+            x.created_at = to_local(time.and_utc())
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string();
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_customs::{AmountArgs, OutputFormat};
+    use crate::database::test_database;
+    use crate::output::write_rows;
+    use crate::process::insert_in_or_out_come;
+
+    async fn history_of_one_record() -> Vec<HistoryRow> {
+        let database = test_database("history-output").await;
+        let args = AmountArgs {
+            amount: "5".parse().unwrap(),
+            add_msg: Some("lunch".to_string()),
+            date: None,
+            account: None,
+            category: None,
+            tags: Vec::new(),
+        };
+        insert_in_or_out_come(&args, database.clone(), false)
+            .await
+            .unwrap();
+        let pool = SqlitePool::connect_with(SqliteConnectOptions::new().filename(&database))
+            .await
+            .unwrap();
+        history_rows(&pool, 10).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn history_in_json() {
+        let rows = history_of_one_record().await;
+        let mut out = Vec::new();
+        assert!(write_rows(OutputFormat::Json, &rows, &mut out).unwrap());
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["command"], "outcome");
+        assert_eq!(json[0]["records"], "1");
+    }
+
+    #[tokio::test]
+    async fn history_in_csv() {
+        let rows = history_of_one_record().await;
+        let mut out = Vec::new();
+        assert!(write_rows(OutputFormat::Csv, &rows, &mut out).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "id,command,records,state,author,created_at");
+        assert!(lines[1].starts_with("1,outcome,1,,"));
+        assert_eq!(lines.len(), 2);
+    }
+}
@@ -0,0 +1,367 @@
+use crate::cli_customs::{LedgerArgs, LedgerCommands, LedgerHeadArgs, VerifyArgs};
+use crate::journal::{RecordImage, record_image};
+use crate::output::print_notice;
+use crate::show::local_text;
+use chrono::Utc;
+use colored::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::prelude::FromRow;
+use sqlx::{SqliteConnection, SqlitePool, sqlite::SqliteConnectOptions};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// the previous hash of the first entry.
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// a change of a record as it goes into the chain, with the json images.
+pub type ChainChange = (i64, Option<String>, Option<String>);
+
+/// an entry of the hash chain.
+#[derive(FromRow, Clone, Debug)]
+struct LedgerEntry {
+    id: i64,
+    command: String,
+    /// `None` for the entry enabling the chain.
+    record_id: Option<i64>,
+    before: Option<String>,
+    after: Option<String>,
+    created_at: String,
+    prev_hash: String,
+    hash: String,
+}
+
+/// the last entry of the chain, to be kept somewhere else.
+#[derive(Serialize, Debug)]
+struct LedgerHead {
+    entries: i64,
+    hash: String,
+    created_at: String,
+}
+
+/// the hash of an entry, chained to the hash before it.
+fn entry_hash(
+    prev_hash: &str,
+    command: &str,
+    record_id: Option<i64>,
+    before: Option<&str>,
+    after: Option<&str>,
+    created_at: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        prev_hash,
+        command,
+        &record_id.map(|x| x.to_string()).unwrap_or_default(),
+        before.unwrap_or(""),
+        after.unwrap_or(""),
+        created_at,
+    ] {
+        // the length keeps the parts apart.
+        hasher.update(part.len().to_string());
+        hasher.update(":");
+        hasher.update(part);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// the hash of the last entry, `None` if the chain is not enabled.
+async fn chain_head(conn: &mut SqliteConnection) -> Result<Option<String>, Box<dyn Error>> {
+    Ok(
+        sqlx::query_scalar("SELECT hash FROM ledger ORDER BY id DESC LIMIT 1")
+            .fetch_optional(&mut *conn)
+            .await?,
+    )
+}
+
+async fn write_chain(
+    conn: &mut SqliteConnection,
+    prev_hash: String,
+    command: &str,
+    changes: &[(Option<i64>, Option<String>, Option<String>)],
+) -> Result<(), Box<dyn Error>> {
+    let created_at = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let query = r#"
+        INSERT INTO ledger (command, record_id, before, after, created_at, prev_hash, hash)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+    "#;
+    let mut prev_hash = prev_hash;
+    for (record_id, before, after) in changes {
+        let hash = entry_hash(
+            &prev_hash,
+            command,
+            *record_id,
+            before.as_deref(),
+            after.as_deref(),
+            &created_at,
+        );
+        sqlx::query(query)
+            .bind(command)
+            .bind(record_id)
+            .bind(before)
+            .bind(after)
+            .bind(&created_at)
+            .bind(&prev_hash)
+            .bind(&hash)
+            .execute(&mut *conn)
+            .await?;
+        prev_hash = hash;
+    }
+    Ok(())
+}
+
+/// append the changes of records by `command` to the hash chain, if it is enabled.
+/// in the transaction of the change, after it is done.
+pub async fn append_chain(
+    conn: &mut SqliteConnection,
+    command: &str,
+    changes: &[ChainChange],
+) -> Result<(), Box<dyn Error>> {
+    let Some(head) = chain_head(conn).await? else {
+        return Ok(());
+    };
+    let changes: Vec<_> = changes
+        .iter()
+        .map(|(id, before, after)| (Some(*id), before.clone(), after.clone()))
+        .collect();
+    write_chain(conn, head, command, &changes).await
+}
+
+/// the json image of a record as the chain keeps it.
+pub fn chain_image(image: Option<&RecordImage>) -> Result<Option<String>, serde_json::Error> {
+    image.map(serde_json::to_string).transpose()
+}
+
+/// dispatch the `ledger` subcommands.
+pub async fn ledger_command(
+    args: &LedgerArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    match &args.command {
+        LedgerCommands::Enable => enable_chain(&pool).await,
+        LedgerCommands::Head(arg) => export_head(arg, &pool).await,
+    }
+}
+
+/// start the chain with the records as they are now.
+async fn enable_chain(pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let mut tx = pool.begin().await?;
+    if chain_head(&mut tx).await?.is_some() {
+        return Err("the hash chain is already enabled".into());
+    }
+
+    let ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM amount_record ORDER BY id")
+        .fetch_all(&mut *tx)
+        .await?;
+    let mut changes = vec![(None, None, None)];
+    for id in &ids {
+        let image = record_image(&mut tx, *id).await?;
+        changes.push((Some(*id), None, chain_image(image.as_ref())?));
+    }
+    write_chain(&mut tx, GENESIS.to_string(), "ledger enable", &changes).await?;
+    tx.commit().await?;
+
+    print_notice(format!(
+        "{}{}",
+        "Enabled the hash chain with records: ".green().bold(),
+        ids.len().to_string().purple().bold()
+    ));
+    Ok(())
+}
+
+/// print or write the head of the chain, for attesting it out of band.
+async fn export_head(args: &LedgerHeadArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let head: Option<(i64, String, String)> = sqlx::query_as(
+        "SELECT (SELECT COUNT(*) FROM ledger), hash, created_at FROM ledger ORDER BY id DESC LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await?;
+    let Some((entries, hash, created_at)) = head else {
+        return Err(
+            "the hash chain is not enabled, enable it with `rustance ledger enable`".into(),
+        );
+    };
+    let head = LedgerHead {
+        entries,
+        hash,
+        created_at: local_text(&created_at),
+    };
+
+    let content = serde_json::to_string_pretty(&head)? + "\n";
+    match args.file {
+        Some(ref file) => {
+            fs::write(file, content).map_err(|e| format!("unable to write `{}`: {}", file, e))?;
+            print_notice(format!(
+                "{}{}",
+                "Wrote the chain head to ".yellow().bold(),
+                file.purple().bold()
+            ));
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+/// check the chain is unbroken and the records are what it says.
+pub async fn verify_command(
+    args: &VerifyArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    let entries: Vec<LedgerEntry> = sqlx::query_as("SELECT * FROM ledger ORDER BY id")
+        .fetch_all(&pool)
+        .await?;
+    if entries.is_empty() {
+        return Err(
+            "the hash chain is not enabled, enable it with `rustance ledger enable`".into(),
+        );
+    }
+
+    let mut problems: Vec<String> = Vec::new();
+    let image = |x: &Option<String>| -> Result<Option<RecordImage>, serde_json::Error> {
+        x.as_deref().map(serde_json::from_str).transpose()
+    };
+    // the image of every record after the last entry of it.
+    let mut expected: HashMap<i64, Option<RecordImage>> = HashMap::new();
+    let mut prev_hash = GENESIS.to_string();
+    for x in &entries {
+        if x.prev_hash != prev_hash {
+            problems.push(format!(
+                "entry #{} does not follow the entry before it",
+                x.id
+            ));
+        }
+        let hash = entry_hash(
+            &x.prev_hash,
+            &x.command,
+            x.record_id,
+            x.before.as_deref(),
+            x.after.as_deref(),
+            &x.created_at,
+        );
+        if hash != x.hash {
+            problems.push(format!("entry #{} ({}) is edited", x.id, x.command));
+        }
+        prev_hash = x.hash.clone();
+
+        let Some(record_id) = x.record_id else {
+            continue;
+        };
+        let (Ok(before), Ok(after)) = (image(&x.before), image(&x.after)) else {
+            problems.push(format!("entry #{} has an unreadable record image", x.id));
+            continue;
+        };
+        if let Some(known) = expected.get(&record_id)
+            && *known != before
+        {
+            problems.push(format!(
+                "record #{} is changed outside the chain before entry #{} ({})",
+                record_id, x.id, x.command
+            ));
+        }
+        expected.insert(record_id, after);
+    }
+
+    let mut conn = pool.acquire().await?;
+    let ids: Vec<i64> = sqlx::query_scalar("SELECT id FROM amount_record ORDER BY id")
+        .fetch_all(&mut *conn)
+        .await?;
+    for id in &ids {
+        if !expected.contains_key(id) {
+            problems.push(format!("record #{} is not in the chain", id));
+        }
+    }
+    let mut chained: Vec<(&i64, &Option<RecordImage>)> = expected.iter().collect();
+    chained.sort_by_key(|x| x.0);
+    for (id, image) in chained {
+        if record_image(&mut conn, *id).await? != *image {
+            problems.push(format!("record #{} differs from the chain", id));
+        }
+    }
+
+    if let Some(ref head) = args.head {
+        match entries.iter().position(|x| x.hash == head.trim()) {
+            Some(i) => print_notice(format!(
+                "{}{}",
+                "The attested head is in the chain, entries after it: "
+                    .yellow()
+                    .bold(),
+                (entries.len() - i - 1).to_string().purple().bold()
+            )),
+            None => problems
+                .push("the attested head is not in the chain, it is rewritten or cut".to_string()),
+        }
+    }
+
+    if problems.is_empty() {
+        print_notice(format!(
+            "{}{}{}{}",
+            "The ledger is intact, entries: ".green().bold(),
+            entries.len().to_string().purple().bold(),
+            ", head: ".green().bold(),
+            prev_hash.purple().bold()
+        ));
+        return Ok(());
+    }
+    for x in &problems {
+        print_notice(format!("{}{}", "Tampered: ".red().bold(), x));
+    }
+    Err(format!(
+        "the ledger fails the verification with {} problems",
+        problems.len()
+    )
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn income(prev_hash: &str) -> String {
+        entry_hash(
+            prev_hash,
+            "income",
+            Some(1),
+            None,
+            Some(r#"{"amount":500}"#),
+            "2025-03-13 21:05:52",
+        )
+    }
+
+    #[test]
+    fn entry_hash_is_stable() {
+        // a change of this hash breaks the chains already written.
+        assert_eq!(
+            income(GENESIS),
+            "16bda67669de26ea86c614f807a6f30f7867db6f26f2dcdc7ecb6cab17f400c8"
+        );
+        assert_eq!(income(GENESIS), income(GENESIS));
+    }
+
+    #[test]
+    fn entry_hash_chains_to_the_hash_before() {
+        let first = income(GENESIS);
+        assert_ne!(income(&first), first);
+    }
+
+    #[test]
+    fn entry_hash_keeps_the_parts_apart() {
+        let hash = |command, created_at| entry_hash(GENESIS, command, None, None, None, created_at);
+        assert_ne!(hash("ab", "c"), hash("a", "bc"));
+        assert_ne!(
+            entry_hash(GENESIS, "patch", Some(1), Some("x"), None, "t"),
+            entry_hash(GENESIS, "patch", Some(1), None, Some("x"), "t")
+        );
+        assert_ne!(
+            entry_hash(GENESIS, "patch", Some(12), None, None, "t"),
+            entry_hash(GENESIS, "patch", Some(1), None, None, "2t")
+        );
+    }
+}
@@ -2,17 +2,57 @@
 
 use clap::Parser;
 use std::error::Error;
+use std::path::Path;
+pub mod account;
+pub mod bill;
+pub mod budget;
+pub mod category;
+pub mod chart;
 pub mod cli_customs;
 pub mod database;
+pub mod dedupe;
+pub mod export;
+pub mod html;
+pub mod import;
+pub mod journal;
+pub mod ledger;
+pub mod money;
+pub mod output;
 pub mod process;
+pub mod recurring;
+pub mod report;
+pub mod search;
+pub mod show;
+pub mod tag;
+pub mod time_utils;
+pub mod trash;
+use account::*;
+use budget::*;
+use category::*;
+use chart::*;
 use cli_customs::*;
 use database::*;
+use dedupe::*;
+use export::*;
+use import::*;
+use journal::*;
+use ledger::*;
+use output::*;
 use process::*;
+use recurring::*;
+use report::*;
+use search::*;
+use show::*;
+use tag::*;
+use trash::*;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let database = init_check_database_all().await;
     let cli = Cli::parse();
+    init_output(cli.output);
+    init_timezone(cli.tz.as_deref())?;
+    let database = init_check_database_all().await;
+    catch_up_recurring(&cli.command, &database).await?;
 
     match &cli.command {
         Commands::ListAll(arg) => {
@@ -30,7 +70,160 @@ async fn main() -> Result<(), Box<dyn Error>> {
         Commands::DeleteRecord(arg) => {
             delete_record(arg, database).await?;
         }
+        Commands::Account(arg) => {
+            account_command(arg, database).await?;
+        }
+        Commands::Transfer(arg) => {
+            transfer(arg, database).await?;
+        }
+        Commands::Category(arg) => {
+            category_command(arg, database).await?;
+        }
+        Commands::Tag(arg) => {
+            tag_command(arg, database).await?;
+        }
+        Commands::Budget(arg) => {
+            budget_command(arg, database).await?;
+        }
+        Commands::Recurring(arg) => {
+            recurring_command(arg, database).await?;
+        }
+        Commands::Export(arg) => {
+            export_command(arg, database).await?;
+        }
+        Commands::Import(arg) => {
+            import_command(arg, database).await?;
+        }
+        Commands::Dedupe(arg) => {
+            dedupe_command(arg, database).await?;
+        }
+        Commands::Undo(arg) => {
+            undo_command(arg, database).await?;
+        }
+        Commands::Redo(arg) => {
+            redo_command(arg, database).await?;
+        }
+        Commands::History(arg) => {
+            history_command(arg, database).await?;
+        }
+        Commands::Trash(arg) => {
+            trash_command(arg, database).await?;
+        }
+        Commands::Show(arg) => {
+            show_command(arg, database).await?;
+        }
+        Commands::Ledger(arg) => {
+            ledger_command(arg, database).await?;
+        }
+        Commands::Verify(arg) => {
+            verify_command(arg, database).await?;
+        }
+        Commands::Report(arg) => {
+            report_command(arg, database).await?;
+        }
+        Commands::Chart(arg) => {
+            chart_command(arg, database).await?;
+        }
+        Commands::Search(arg) => {
+            search_command(arg, database).await?;
+        }
     }
 
     Ok(())
 }
+
+/// add the due recurring records before a command reading or writing records,
+/// so it sees the rent and salary already due.
+async fn catch_up_recurring(command: &Commands, database: &Path) -> Result<(), Box<dyn Error>> {
+    match uses_records(command) {
+        true => run_recurring_on_start(database).await,
+        false => Ok(()),
+    }
+}
+
+/// whether the command reads or writes records.
+/// the dry runs and the commands only changing the settings leave the records as they are,
+/// `recurring run` adds the due records itself, so `--dry-run` can still show them.
+/// `undo` would undo the records added here instead of the last change,
+/// and `verify` checks the records as they are.
+fn uses_records(command: &Commands) -> bool {
+    match command {
+        Commands::ListAll(_)
+        | Commands::Income(_)
+        | Commands::Outcome(_)
+        | Commands::Transfer(_)
+        | Commands::Tag(_)
+        | Commands::Export(_)
+        | Commands::Dedupe(_)
+        | Commands::Show(_)
+        | Commands::Report(_)
+        | Commands::Chart(_)
+        | Commands::Search(_) => true,
+        Commands::PatchRecord(arg) => !arg.dry_run,
+        Commands::DeleteRecord(arg) => !arg.dry_run,
+        Commands::Account(arg) => matches!(arg.command, AccountCommands::List(_)),
+        Commands::Category(arg) => matches!(arg.command, CategoryCommands::List),
+        Commands::Budget(arg) => matches!(arg.command, BudgetCommands::List(_)),
+        Commands::Import(arg) => !match &arg.command {
+            ImportCommands::Csv(x) => x.dry_run,
+            ImportCommands::Alipay(x) | ImportCommands::Wechat(x) => x.dry_run,
+            ImportCommands::Rustance(x) => x.dry_run,
+        },
+        Commands::Recurring(_)
+        | Commands::Undo(_)
+        | Commands::Redo(_)
+        | Commands::History(_)
+        | Commands::Trash(_)
+        | Commands::Ledger(_)
+        | Commands::Verify(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+    use time_utils::local_today;
+
+    fn parse(args: &[&str]) -> Commands {
+        Cli::try_parse_from([&["rustance"], args].concat())
+            .unwrap()
+            .command
+    }
+
+    async fn record_count(database: &Path) -> i64 {
+        let pool = SqlitePool::connect_with(SqliteConnectOptions::new().filename(database))
+            .await
+            .unwrap();
+        sqlx::query_scalar("SELECT count(*) FROM amount_record")
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn due_recurring_records_show_up_in_list_all() {
+        let database = test_database("recurring-list-all").await;
+        let start = (local_today() - Duration::days(40)).to_string();
+        let Commands::Recurring(arg) = parse(&["recurring", "add", "1200", "rent", "-s", &start])
+        else {
+            unreachable!()
+        };
+        recurring_command(&arg, database.clone()).await.unwrap();
+
+        for dry_run in [
+            parse(&["delete-record", "1", "--dry-run"]),
+            parse(&["budget", "set", "100"]),
+            parse(&["undo"]),
+        ] {
+            catch_up_recurring(&dry_run, &database).await.unwrap();
+        }
+        assert_eq!(record_count(&database).await, 0);
+
+        catch_up_recurring(&parse(&["list-all"]), &database)
+            .await
+            .unwrap();
+        assert_eq!(record_count(&database).await, 2);
+    }
+}
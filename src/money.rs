@@ -0,0 +1,249 @@
+use std::fmt;
+use std::ops::{Neg, Sub};
+use std::str::FromStr;
+
+/// An exact amount of money, stored as integer minor units (cents).
+///
+/// User input is parsed as a decimal string and never goes through a float,
+/// so `0.29` is always stored as `29`.
+#[derive(sqlx::Type, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[sqlx(transparent)]
+pub struct Money(i64);
+
+impl Money {
+    /// The number of digits allowed after the decimal point.
+    pub const FRACTION_DIGITS: u32 = 2;
+
+    /// minor units in one major unit.
+    const SCALE: i64 = 10i64.pow(Self::FRACTION_DIGITS);
+
+    pub const ZERO: Money = Money(0);
+
+    /// The largest amount parsed, `999999999999.99`.
+    /// far below the limit of i64, so the totals of many records still fit.
+    pub const MAX: Money = Money(10i64.pow(12) * Self::SCALE - 1);
+
+    pub fn from_minor(minor: i64) -> Self {
+        Money(minor)
+    }
+
+    pub fn minor(self) -> i64 {
+        self.0
+    }
+
+    pub fn abs(self) -> Self {
+        Money(self.0.abs())
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    /// `self + rhs`, an error instead of an overflow.
+    pub fn checked_add(self, rhs: Money) -> Result<Money, String> {
+        self.0
+            .checked_add(rhs.0)
+            .map(Money)
+            .ok_or("the total amount is too large".to_string())
+    }
+
+    /// the sum of `amounts`, an error instead of an overflow.
+    pub fn total(amounts: impl IntoIterator<Item = Money>) -> Result<Money, String> {
+        amounts
+            .into_iter()
+            .try_fold(Money::ZERO, Money::checked_add)
+    }
+
+    /// the amount with the sign of the direction, income is positive.
+    pub fn signed(self, in_or_out: bool) -> Self {
+        if in_or_out { self } else { -self }
+    }
+}
+
+impl FromStr for Money {
+    type Err = String;
+
+    /// Parse a decimal string like `100`, `0.29` or `-1500.16`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let trimmed = input.trim();
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+
+        if int_part.is_empty() && frac_part.is_empty()
+            || !int_part.chars().all(|c| c.is_ascii_digit())
+            || !frac_part.chars().all(|c| c.is_ascii_digit())
+        {
+            return Err(format!("invalid amount `{}`", input));
+        }
+        if frac_part.len() > Self::FRACTION_DIGITS as usize {
+            return Err(format!(
+                "invalid amount `{}`, at most {} digits after the decimal point",
+                input,
+                Self::FRACTION_DIGITS
+            ));
+        }
+
+        let too_large = || format!("amount `{}` is too large, at most {}", input, Self::MAX);
+        let major: i64 = if int_part.is_empty() {
+            0
+        } else {
+            int_part.parse().map_err(|_| too_large())?
+        };
+        let minor: i64 = format!(
+            "{:0<width$}",
+            frac_part,
+            width = Self::FRACTION_DIGITS as usize
+        )
+        .parse()
+        .unwrap_or(0);
+        let value = major
+            .checked_mul(Self::SCALE)
+            .and_then(|x| x.checked_add(minor))
+            .filter(|x| *x <= Self::MAX.0)
+            .ok_or_else(too_large)?;
+
+        Ok(Money(if negative { -value } else { value }))
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        let scale = Self::SCALE as u64;
+        let text = format!(
+            "{}{}.{:0width$}",
+            sign,
+            abs / scale,
+            abs % scale,
+            width = Self::FRACTION_DIGITS as usize
+        );
+        f.pad(&text)
+    }
+}
+
+/// serialized as a decimal string like `12.50`, so no precision is lost.
+impl serde::Serialize for Money {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+/// clap value parser for the amount of a record, which must be greater than 0.
+pub fn parse_amount(input: &str) -> Result<Money, String> {
+    let amount: Money = input.parse()?;
+    if !amount.is_positive() {
+        return Err(format!("amount `{}` must be greater than 0", input));
+    }
+    Ok(amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_decimal_strings() {
+        let minor = |x: &str| x.parse::<Money>().map(Money::minor);
+        assert_eq!(minor("100"), Ok(10000));
+        assert_eq!(minor("0.29"), Ok(29));
+        assert_eq!(minor("1500.16"), Ok(150016));
+        assert_eq!(minor(".5"), Ok(50));
+        assert_eq!(minor("3."), Ok(300));
+        assert_eq!(minor(" +7.1 "), Ok(710));
+        assert_eq!(minor("-1500.16"), Ok(-150016));
+    }
+
+    #[test]
+    fn parse_rejects_more_digits_than_cents() {
+        assert_eq!(
+            "0.291".parse::<Money>(),
+            Err("invalid amount `0.291`, at most 2 digits after the decimal point".to_string())
+        );
+        assert!("12.000".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn parse_rejects_invalid_amounts() {
+        for x in [
+            "",
+            ".",
+            "-",
+            "abc",
+            "1,5",
+            "1.2.3",
+            "1e3",
+            "--1",
+            "92233720368547758.08",
+        ] {
+            assert!(x.parse::<Money>().is_err(), "`{}` is parsed", x);
+        }
+    }
+
+    #[test]
+    fn parse_caps_the_amount() {
+        assert_eq!("999999999999.99".parse::<Money>(), Ok(Money::MAX));
+        assert_eq!("-999999999999.99".parse::<Money>(), Ok(-Money::MAX));
+        assert_eq!(
+            "1000000000000".parse::<Money>(),
+            Err("amount `1000000000000` is too large, at most 999999999999.99".to_string())
+        );
+        assert!("999999999999.991".parse::<Money>().is_err());
+        assert!(parse_amount("92233720368547758.07").is_err());
+    }
+
+    #[test]
+    fn total_is_an_error_instead_of_an_overflow() {
+        assert_eq!(
+            Money::total([Money::MAX, Money::MAX]),
+            Ok(Money::from_minor(Money::MAX.minor() * 2))
+        );
+        assert_eq!(Money::total([]), Ok(Money::ZERO));
+        assert_eq!(
+            Money::total(vec![Money::MAX; 100_000]),
+            Err("the total amount is too large".to_string())
+        );
+        assert!(
+            Money::from_minor(i64::MAX)
+                .checked_add(Money::from_minor(1))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_amount_must_be_positive() {
+        assert_eq!(parse_amount("0.01").map(Money::minor), Ok(1));
+        assert!(parse_amount("0").is_err());
+        assert!(parse_amount("-5").is_err());
+    }
+
+    #[test]
+    fn display_pads_the_cents() {
+        assert_eq!(Money::from_minor(5).to_string(), "0.05");
+        assert_eq!(Money::from_minor(-150016).to_string(), "-1500.16");
+        assert_eq!(format!("{:>7}", Money::from_minor(1250)), "  12.50");
+    }
+}
@@ -0,0 +1,172 @@
+use crate::cli_customs::OutputFormat;
+use crate::export::ExportRecord;
+use serde::Serialize;
+use std::error::Error;
+use std::fmt::Display;
+use std::io::{self, IsTerminal, Write};
+use std::sync::OnceLock;
+
+static OUTPUT: OnceLock<OutputFormat> = OnceLock::new();
+
+/// Set the `--output` format, colors are turned off
+/// unless tables are written to a terminal.
+pub fn init_output(format: OutputFormat) {
+    let _ = OUTPUT.set(format);
+    if !colored_output() {
+        colored::control::set_override(false);
+    }
+}
+
+/// The `--output` format.
+pub fn output_format() -> OutputFormat {
+    *OUTPUT.get_or_init(OutputFormat::default)
+}
+
+/// Whether the data goes to stdout as json or csv,
+/// then everything meant for humans goes to stderr.
+pub fn is_machine_output() -> bool {
+    matches!(output_format(), OutputFormat::Json | OutputFormat::Csv)
+}
+
+/// Whether the output is colored.
+pub fn colored_output() -> bool {
+    output_format() == OutputFormat::Table && io::stdout().is_terminal()
+}
+
+/// The width of the terminal, from `COLUMNS` or 80 if stdout is not one.
+pub fn terminal_width() -> usize {
+    if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
+        return width as usize;
+    }
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(80)
+}
+
+/// Remove the ansi escape sequences, e.g. the colors of tabled.
+fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // `ESC [ params letter`
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if c.is_ascii_alphabetic() {
+                        break;
+                    }
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// Print a table followed by a blank line.
+pub fn print_table(table: String) {
+    let table = if colored_output() {
+        table
+    } else {
+        strip_ansi(&table)
+    };
+    print_notice(format!("{table}\n"));
+}
+
+/// Print a line meant for humans, to stderr if the data goes to stdout.
+pub fn print_notice(line: impl Display) {
+    if is_machine_output() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Ask a question and read the trimmed, lowercased answer from stdin.
+/// fails with `hint` when stdin is not a terminal, as nobody could answer.
+pub fn ask(prompt: impl Display, hint: &str) -> Result<String, Box<dyn Error>> {
+    if !io::stdin().is_terminal() {
+        return Err(not_terminal(hint));
+    }
+    print_notice(prompt);
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_lowercase())
+}
+
+/// the hint of `confirm` when it can not ask.
+const CONFIRM_HINT: &str = "pass --yes to confirm without asking, or --dry-run to only preview";
+
+fn not_terminal(hint: &str) -> Box<dyn Error> {
+    format!("stdin is not a terminal to answer the question, {}", hint).into()
+}
+
+/// Ask to confirm with Yes(YES/yes/Y/y), for the commands with `--yes` and `--dry-run`.
+pub fn confirm(prompt: impl Display) -> Result<bool, Box<dyn Error>> {
+    let input = ask(prompt, CONFIRM_HINT)?;
+    Ok(["yes", "y"].contains(&input.as_str()))
+}
+
+/// Fail before printing anything if `confirm` will be asked but can not be answered.
+pub fn check_confirm(yes: bool, dry_run: bool) -> Result<(), Box<dyn Error>> {
+    match yes || dry_run || io::stdin().is_terminal() {
+        true => Ok(()),
+        false => Err(not_terminal(CONFIRM_HINT)),
+    }
+}
+
+/// Print `value` as pretty json.
+pub fn print_json<T: Serialize + ?Sized>(value: &T) -> Result<(), Box<dyn Error>> {
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer_pretty(&mut stdout, value)?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+/// Print rows as csv with a header.
+pub fn print_csv<T: Serialize>(rows: &[T]) -> Result<(), Box<dyn Error>> {
+    write_rows(OutputFormat::Csv, rows, io::stdout().lock()).map(|_| ())
+}
+
+/// Write the rows of a table as a json array or as csv with a header,
+/// `false` for the tables, which the commands draw themselves.
+pub fn write_rows<T: Serialize>(
+    format: OutputFormat,
+    rows: &[T],
+    mut writer: impl Write,
+) -> Result<bool, Box<dyn Error>> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, rows)?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(writer);
+            for x in rows {
+                writer.serialize(x)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table | OutputFormat::Plain => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Print the rows of a table in the machine output.
+/// `true` if they are printed, then the command leaves out the table.
+pub fn print_rows<T: Serialize>(rows: &[T]) -> Result<bool, Box<dyn Error>> {
+    write_rows(output_format(), rows, io::stdout().lock())
+}
+
+/// Print the records a command created or changed in the machine output.
+/// a single record is a json object, more are a json array.
+pub fn print_records(records: &[ExportRecord]) -> Result<(), Box<dyn Error>> {
+    match output_format() {
+        OutputFormat::Json if records.len() == 1 => print_json(&records[0]),
+        OutputFormat::Json => print_json(records),
+        OutputFormat::Csv => print_csv(records),
+        OutputFormat::Table | OutputFormat::Plain => Ok(()),
+    }
+}
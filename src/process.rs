@@ -1,10 +1,30 @@
 use crate::PatchRecordArgs;
-use crate::cli_customs::{AmountArgs, DeleteRecordArgs, ListAllArgs};
-use chrono::{Datelike, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use crate::account::{
+    AccountBalance, account_balances, format_output_account_balances, resolve_account,
+};
+use crate::budget::{BudgetStatus, month_budget_status, warn_over_budget};
+use crate::category::{category_subtotals, resolve_category};
+use crate::cli_customs::OutputFormat;
+use crate::cli_customs::{
+    AmountArgs, DeleteRecordArgs, IdRange, ListAllArgs, RecordFilterArgs, RecordSelectArgs,
+    TransferArgs,
+};
+use crate::export::{ExportRecord, export_records};
+use crate::journal::{inserted, journal, record_images};
+use crate::money::Money;
+use crate::output::{
+    check_confirm, confirm, is_machine_output, output_format, print_csv, print_json, print_notice,
+    print_records, print_table,
+};
+use crate::tag::{add_record_tags, normalize_tag, parse_tags, remove_record_tags};
+use crate::time_utils::{DateRange, parse_occurred_at, to_local};
+use chrono::Utc;
 use colored::*;
+use regex::Regex;
+use serde::Serialize;
 use sqlx::prelude::FromRow;
-use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
-use std::collections::HashMap;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool, sqlite::SqliteConnectOptions};
+use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::path::PathBuf;
 use tabled::{
@@ -22,130 +42,346 @@ use tabled::{
 #[derive(FromRow, Clone, Debug, Tabled)]
 pub struct Amount {
     pub id: i32,
-    pub amount: u32,
+    pub amount: Money,
     pub in_or_out: bool,
     pub append_msg: String,
+    #[tabled(skip)]
+    pub account_id: Option<i64>,
+    /// the name of the account, joined from `account`.
+    #[tabled(skip)]
+    pub account: Option<String>,
+    /// both sides of a transfer share it, `None` for income and outcome.
+    #[tabled(skip)]
+    pub transfer_id: Option<i64>,
+    #[tabled(skip)]
+    pub category_id: Option<i64>,
+    /// the full path of the category, e.g. `Food:Groceries`.
+    #[tabled(skip)]
+    pub category: Option<String>,
+    /// the tags of the record separated by spaces.
+    #[tabled(skip)]
+    pub tags: Option<String>,
+    /// where an imported record comes from, e.g. `alipay`.
+    #[tabled(skip)]
+    pub source: Option<String>,
+    /// the id of an imported record in its source.
+    #[tabled(skip)]
+    pub external_id: Option<String>,
+    /// when the money actually moved, used for grouping and filtering.
+    pub occurred_at: chrono::DateTime<chrono::Utc>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// audit metadata only, bumped on every patch.
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// when the record is moved to the trash, `None` for the live records.
+    #[tabled(skip)]
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Amount {
+    /// the amount with its direction, income is positive.
+    pub fn signed_amount(&self) -> Money {
+        self.amount.signed(self.in_or_out)
+    }
+
+    /// the amount counted in the income and outcome statistics,
+    /// transfers only move money between accounts and count as zero.
+    pub fn statistic_amount(&self) -> Money {
+        match self.transfer_id {
+            Some(_) => Money::ZERO,
+            None => self.signed_amount(),
+        }
+    }
 }
 
 /// precise data for output
-/// the different between Amount and AmountPrecise is that
-/// AmountPrecise's amount is float (amount / 100.0f32).
+/// the amount is a `Money` of exact i64 minor units (cents) like in `Amount`,
+/// the different is that AmountPrecise's time is formatted in local time.
 #[derive(Clone, Debug, Tabled)]
 
 pub struct AmountPrecise {
     pub id: i32,
-    pub amount: f32,
+    pub amount: Money,
     pub in_or_out: bool,
     pub append_msg: String,
-    // pub created_at: String,
-    pub updated_at: String,
+    pub account: String,
+    pub category: String,
+    pub tags: String,
+    pub occurred_at: String,
+}
+
+/// the `list-all` data of the json output.
+#[derive(Serialize, Debug)]
+struct ListAllReport {
+    months: Vec<MonthReport>,
+    /// only without any filter.
+    balances: Option<Vec<AccountBalance>>,
+    /// only without the month filter.
+    total: Option<Money>,
+}
+
+/// the records and statistics of a month.
+#[derive(Serialize, Debug)]
+struct MonthReport {
+    month: String,
+    records: Vec<ExportRecord>,
+    total: Money,
+    category_totals: Vec<CategoryTotal>,
+    budgets: Vec<BudgetReport>,
+}
+
+#[derive(Serialize, Debug)]
+struct CategoryTotal {
+    category: String,
+    total: Money,
+}
+
+#[derive(Serialize, Debug)]
+struct BudgetReport {
+    /// `None` for the overall budget.
+    category: Option<String>,
+    budget: Money,
+    spent: Money,
+    remaining: Money,
+}
+
+/// select `Amount` with its account name, category path and tags.
+/// filter with `amount_record.` prefixed columns where they are ambiguous,
+/// and leave out the records in the trash with `amount_record.deleted_at IS NULL`.
+pub const AMOUNT_SELECT: &str = r#"
+    SELECT
+        amount_record.id, amount, in_or_out, append_msg,
+        account_id, account.name AS account, transfer_id,
+        category_id, category_path.path AS category,
+        (
+            SELECT group_concat(name, ' ') FROM (
+                SELECT tag.name FROM record_tag JOIN tag ON tag.id = record_tag.tag_id
+                WHERE record_tag.record_id = amount_record.id ORDER BY tag.name
+            )
+        ) AS tags,
+        source, external_id,
+        occurred_at, amount_record.created_at, amount_record.updated_at, amount_record.deleted_at
+    FROM
+        amount_record
+        LEFT JOIN account ON account.id = amount_record.account_id
+        LEFT JOIN category_path ON category_path.id = amount_record.category_id
+"#;
+
+/// narrow the query to the moments of `column` in `range`.
+pub fn push_date_range(query: &mut QueryBuilder<Sqlite>, column: &str, range: &DateRange) {
+    let (start, end) = range.utc_bounds();
+    if let Some(start) = start {
+        query
+            .push(format!(" AND {} >= ", column))
+            .push_bind(start.format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+    if let Some(end) = end {
+        query
+            .push(format!(" AND {} < ", column))
+            .push_bind(end.format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+}
+
+/// select the records matching `filter`, in the order they happened.
+pub async fn select_records(
+    pool: &SqlitePool,
+    filter: &RecordFilterArgs,
+) -> Result<Vec<Amount>, Box<dyn Error>> {
+    let mut query = QueryBuilder::<Sqlite>::new(AMOUNT_SELECT);
+    query.push(" WHERE amount_record.deleted_at IS NULL");
+
+    push_date_range(&mut query, "occurred_at", &filter.range.range()?);
+    if let Some(ref account) = filter.account {
+        query.push(" AND account.name = ").push_bind(account.trim());
+    }
+    for (tags, exists) in [(&filter.tags, "EXISTS"), (&filter.not_tags, "NOT EXISTS")] {
+        for tag in tags {
+            query
+                .push(format!(
+                    " AND {} (SELECT 1 FROM record_tag JOIN tag ON tag.id = record_tag.tag_id WHERE record_tag.record_id = amount_record.id AND tag.name = ",
+                    exists
+                ))
+                .push_bind(normalize_tag(tag)?)
+                .push(")");
+        }
+    }
+    query.push(" ORDER BY occurred_at, amount_record.id");
+
+    Ok(query.build_query_as().fetch_all(pool).await?)
+}
+
+/// select the records in `range`, in the order they happened.
+pub async fn select_range_records(
+    pool: &SqlitePool,
+    range: &DateRange,
+) -> Result<Vec<Amount>, Box<dyn Error>> {
+    let mut query = QueryBuilder::<Sqlite>::new(AMOUNT_SELECT);
+    query.push(" WHERE amount_record.deleted_at IS NULL");
+    push_date_range(&mut query, "occurred_at", range);
+    query.push(" ORDER BY occurred_at, amount_record.id");
+
+    Ok(query.build_query_as().fetch_all(pool).await?)
 }
 
 /// List all the Wallet Balances.
-/// if the args.time is set, will present the specific month's data.
+/// if a period is set, will present the data of its months.
 pub async fn list_all(args: &ListAllArgs, database_path: PathBuf) -> Result<(), Box<dyn Error>> {
     let options = SqliteConnectOptions::new().filename(&database_path);
     let pool = SqlitePool::connect_with(options).await?;
 
-    let query = r#"
-        SELECT 
-            id, amount, in_or_out, append_msg, created_at, updated_at 
-        FROM 
-            amount_record
-    "#;
+    let amount = select_records(&pool, &args.filter).await?;
 
-    let amount = match args.time {
-        Some(ref time) => {
-            let current_month = inverse_utc8(
-                NaiveDate::parse_from_str(&format!("{}-01", time), "%Y-%m-%d")
-                    .expect("time format error."),
-            );
-
-            let next_month = current_month.with_month(current_month.month() + 1).unwrap();
-            let query = format!("{} WHERE updated_at BETWEEN ? AND ?", query);
-            sqlx::query_as(&query)
-                .bind(current_month.format("%Y-%m-%d %H:%M:%S").to_string())
-                .bind(next_month.format("%Y-%m-%d %H:%M:%S").to_string())
-                .fetch_all(&pool)
-                .await
-                .expect("error when select the amount_record with time range specified")
+    match output_format() {
+        OutputFormat::Json => {
+            return print_json(&list_all_report(&pool, &args.filter, &amount).await?);
         }
-        None => sqlx::query_as(query)
-            .fetch_all(&pool)
-            .await
-            .expect("error with selecting the amount_record"),
-    };
+        OutputFormat::Csv => return print_csv(&export_records(&pool, &amount).await?),
+        OutputFormat::Table | OutputFormat::Plain => {}
+    }
 
     let grouped_amount = group_amount_by_month(&amount);
     let mut sorted_months: Vec<String> = grouped_amount.keys().cloned().collect();
     sorted_months.sort_unstable();
 
     for month in sorted_months {
-        format_output_month_total(&grouped_amount[&month], month).expect("output error.");
-    }
-
-    match args.time {
-        Some(_) => (),
-        None => println!("{}{}", "Total: ".blue().bold(), {
-            let hundred_amount = amount.iter().fold(0, |acc, x| {
-                acc + x.amount as i32 * if x.in_or_out { 1 } else { -1 }
-            }) as f32
-                / 100.0;
-            hundred_amount.to_string().purple().italic()
-        }),
-    };
+        let budgets = month_budget_status(&pool, &month).await?;
+        format_output_month_total(&grouped_amount[&month], month, &budgets)?;
+    }
+
+    let filter = &args.filter;
+    if filter.range.is_empty() {
+        if is_unfiltered(filter) {
+            format_output_account_balances(&account_balances(&pool, true).await?)?;
+        }
+        println!("{}{}", "Total: ".blue().bold(), {
+            let total = Money::total(amount.iter().map(Amount::signed_amount))?;
+            total.to_string().purple().italic()
+        });
+    }
 
     Ok(())
 }
 
-fn inverse_utc8(current: NaiveDate) -> NaiveDateTime {
-    let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
-    let current_month_midnight = NaiveDateTime::new(current, midnight);
+/// whether `list-all` shows every record, the account balances are only shown then.
+fn is_unfiltered(filter: &RecordFilterArgs) -> bool {
+    filter.account.is_none() && filter.tags.is_empty() && filter.not_tags.is_empty()
+}
+
+/// the same data as the `list-all` tables, for the json output.
+async fn list_all_report(
+    pool: &SqlitePool,
+    filter: &RecordFilterArgs,
+    amount: &Vec<Amount>,
+) -> Result<ListAllReport, Box<dyn Error>> {
+    let grouped_amount = group_amount_by_month(amount);
+    let mut sorted_months: Vec<String> = grouped_amount.keys().cloned().collect();
+    sorted_months.sort_unstable();
+
+    let mut months = Vec::new();
+    for month in sorted_months {
+        let records = &grouped_amount[&month];
+        months.push(MonthReport {
+            records: export_records(pool, records).await?,
+            total: Money::total(records.iter().map(Amount::statistic_amount))?,
+            category_totals: category_subtotals(records)?
+                .into_iter()
+                .map(|(category, total)| CategoryTotal { category, total })
+                .collect(),
+            budgets: month_budget_status(pool, &month)
+                .await?
+                .into_iter()
+                .map(|x| BudgetReport {
+                    remaining: x.remaining(),
+                    category: x.category,
+                    budget: x.budget,
+                    spent: x.spent,
+                })
+                .collect(),
+            month,
+        });
+    }
 
-    current_month_midnight - chrono::Duration::hours(8)
+    let (balances, total) = match filter.range.is_empty() {
+        false => (None, None),
+        true => (
+            match is_unfiltered(filter) {
+                true => Some(account_balances(pool, true).await?),
+                false => None,
+            },
+            Some(Money::total(amount.iter().map(Amount::signed_amount))?),
+        ),
+    };
+    Ok(ListAllReport {
+        months,
+        balances,
+        total,
+    })
 }
 
 /// use tabled to format the output.
-/// this function only format a single month.
-fn format_output_month_total(amount: &[Amount], month: String) -> Result<(), Box<dyn Error>> {
+/// this function only format a single month,
+/// `budgets` of the month are shown under the statistics.
+pub fn format_output_month_total(
+    amount: &[Amount],
+    month: String,
+    budgets: &[BudgetStatus],
+) -> Result<(), Box<dyn Error>> {
     let mut amount_precise: Vec<AmountPrecise> = amount
         .iter()
         .map(|x| {
-            let offset: FixedOffset = FixedOffset::east_opt(8 * 3600).unwrap();
-            let updated_at_utc_plus_8 = x.updated_at.with_timezone(&offset);
-            let updated_at_str = updated_at_utc_plus_8
+            let occurred_at_str = to_local(x.occurred_at)
                 .format("%Y-%m-%d %H:%M:%S")
                 .to_string();
 
             AmountPrecise {
                 id: x.id,
-                amount: x.amount as f32 / 100.0,
+                amount: x.amount,
                 in_or_out: x.in_or_out,
                 append_msg: x.append_msg.clone(),
-                updated_at: updated_at_str,
+                account: x.account.clone().unwrap_or_default(),
+                category: x.category.clone().unwrap_or_default(),
+                tags: x.tags.clone().unwrap_or_default(),
+                occurred_at: occurred_at_str,
             }
         })
         .collect();
 
-    let month_total = amount.iter().fold(0, |acc, x| {
-        acc + x.amount as i32 * if x.in_or_out { 1 } else { -1 }
-    }) as f32
-        / 100.0;
-    let (abs_month_total, month_in_or_out) = if month_total > 0.0 {
-        (month_total, true)
-    } else {
-        (-month_total, false)
-    };
+    let month_total = Money::total(amount.iter().map(Amount::statistic_amount))?;
     amount_precise.push(AmountPrecise {
         id: 0,
-        amount: abs_month_total,
-        in_or_out: month_in_or_out,
+        amount: month_total.abs(),
+        in_or_out: month_total.is_positive(),
         append_msg: "Month Total".to_string(),
-        // created_at: "".to_string(),
-        updated_at: "".to_string(),
+        account: "".to_string(),
+        category: "".to_string(),
+        tags: "".to_string(),
+        occurred_at: "".to_string(),
     });
+    amount_precise.extend(
+        category_subtotals(amount)?
+            .into_iter()
+            .map(|(path, total)| AmountPrecise {
+                id: 0,
+                amount: total.abs(),
+                in_or_out: total.is_positive(),
+                append_msg: "Category Total".to_string(),
+                account: "".to_string(),
+                category: path,
+                tags: "".to_string(),
+                occurred_at: "".to_string(),
+            }),
+    );
+    amount_precise.extend(budgets.iter().map(|x| AmountPrecise {
+        id: 0,
+        amount: x.remaining().abs(),
+        in_or_out: !x.remaining().is_negative(),
+        append_msg: format!("Budget Left ({} / {})", x.spent, x.budget),
+        account: "".to_string(),
+        category: x.label(),
+        tags: "".to_string(),
+        occurred_at: "".to_string(),
+    }));
+    // the panel and the header come before the records.
+    let statistics_row = amount.len() + 2;
 
     let mut table = Table::new(amount_precise);
     table
@@ -155,6 +391,9 @@ fn format_output_month_total(amount: &[Amount], month: String) -> Result<(), Box
             Color::FG_BRIGHT_BLUE,
             Color::FG_BRIGHT_RED,
             Color::FG_BRIGHT_CYAN,
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_YELLOW,
+            Color::FG_BRIGHT_GREEN,
             Color::FG_MAGENTA,
         ]))
         .with(Panel::header(format!("Accounts Of the Month {}\n", month)))
@@ -183,12 +422,17 @@ fn format_output_month_total(amount: &[Amount], month: String) -> Result<(), Box
         )
         .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
         .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
-        .with(Highlight::new(Rows::last()).color(BorderColor::default().top(Color::FG_YELLOW)))
-        .with(Highlight::new(Rows::last()).border(Border::new().top('━')))
-        .with(LineText::new("Statistics", Rows::last()).color(Color::BG_YELLOW | Color::FG_BLACK))
-        .to_string();
+        .with(
+            Highlight::new(Rows::single(statistics_row))
+                .color(BorderColor::default().top(Color::FG_YELLOW)),
+        )
+        .with(Highlight::new(Rows::single(statistics_row)).border(Border::new().top('━')))
+        .with(
+            LineText::new("Statistics", Rows::single(statistics_row))
+                .color(Color::BG_YELLOW | Color::FG_BLACK),
+        );
 
-    println!("{table}\n");
+    print_table(table.to_string());
 
     Ok(())
 }
@@ -196,19 +440,43 @@ fn format_output_month_total(amount: &[Amount], month: String) -> Result<(), Box
 /// group the amount by month.
 /// the input amount may include a lot of month, and
 /// output is a hashset, which key is the format: "year-month".
-fn group_amount_by_month(amount: &Vec<Amount>) -> HashMap<String, Vec<Amount>> {
+pub fn group_amount_by_month(amount: &Vec<Amount>) -> HashMap<String, Vec<Amount>> {
     let mut month_map: HashMap<String, Vec<Amount>> = HashMap::new();
 
     for record in amount {
-        let offset: FixedOffset = FixedOffset::east_opt(8 * 3600).unwrap();
-        let updated_at_utc_plus_8 = record.updated_at.with_timezone(&offset);
-        let month = updated_at_utc_plus_8.format("%Y-%m").to_string();
-        month_map.entry(month).or_default().push(record.to_owned());
+        month_map
+            .entry(record_month(record))
+            .or_default()
+            .push(record.to_owned());
     }
 
     month_map
 }
 
+/// the space separated tags of a record after adding `tags` and removing `untags`.
+fn patch_tags(current: Option<&str>, tags: &[String], untags: &[String]) -> Option<String> {
+    let mut now: Vec<String> = current
+        .unwrap_or("")
+        .split_whitespace()
+        .map(str::to_string)
+        .chain(tags.iter().cloned())
+        .filter(|x| !untags.contains(x))
+        .collect();
+    now.sort_unstable();
+    now.dedup();
+    Some(now.join(" "))
+}
+
+/// the "year-month" a record belongs to, in local time.
+fn record_month(record: &Amount) -> String {
+    record_month_of(record.occurred_at)
+}
+
+/// the "year-month" of a moment, in local time.
+fn record_month_of(occurred_at: chrono::DateTime<Utc>) -> String {
+    to_local(occurred_at).format("%Y-%m").to_string()
+}
+
 /// insert a record
 pub async fn insert_in_or_out_come(
     args: &AmountArgs,
@@ -218,22 +486,46 @@ pub async fn insert_in_or_out_come(
     let options = SqliteConnectOptions::new().filename(&database_path);
     let pool = SqlitePool::connect_with(options).await?;
 
+    let occurred_at = match args.date {
+        Some(ref date) => parse_occurred_at(date)?,
+        None => Utc::now(),
+    };
+    let account_id = resolve_account(&pool, args.account.as_deref()).await?;
+    let category_id = match args.category {
+        Some(ref category) => Some(resolve_category(&pool, category).await?),
+        None => None,
+    };
+    let tags = parse_tags(args.add_msg.as_deref(), &args.tags)?;
+
     let query = r#"
-        INSERT INTO amount_record (amount, in_or_out, append_msg, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?)
+        INSERT INTO amount_record (amount, in_or_out, append_msg, account_id, category_id, occurred_at, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
     "#;
 
-    sqlx::query(query)
-        .bind((args.amount * 100.0) as u32)
+    let mut tx = pool.begin().await?;
+    let record_id = sqlx::query(query)
+        .bind(args.amount)
         .bind(in_or_out)
         .bind(args.add_msg.clone().unwrap_or("".to_string()))
+        .bind(account_id)
+        .bind(category_id)
+        .bind(occurred_at.format("%Y-%m-%d %H:%M:%S").to_string())
         .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
         .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
-        .execute(&pool)
+        .execute(&mut *tx)
         .await
-        .expect("error when insert the amount_record");
-
-    println!(
+        .expect("error when insert the amount_record")
+        .last_insert_rowid();
+    add_record_tags(&mut tx, record_id, &tags).await?;
+    journal(
+        &mut tx,
+        if in_or_out { "income" } else { "outcome" },
+        inserted(&[record_id]),
+    )
+    .await?;
+    tx.commit().await?;
+
+    print_notice(format!(
         "{}{}{}: {}",
         "Inserted ".yellow().bold(),
         if in_or_out {
@@ -243,153 +535,403 @@ pub async fn insert_in_or_out_come(
         },
         "record with amount".yellow().bold(),
         args.amount.to_string().purple().bold()
+    ));
+    if is_machine_output() {
+        let record = select_record_unit(&pool, record_id as i32).await?;
+        print_records(&export_records(&pool, &record).await?)?;
+    }
+    if !in_or_out {
+        warn_over_budget(&pool, &record_month_of(occurred_at), category_id).await?;
+    }
+
+    Ok(())
+}
+
+/// move money between two accounts.
+/// writes a linked pair of records which is left out of the month statistics.
+pub async fn transfer(args: &TransferArgs, database_path: PathBuf) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    let from = resolve_account(&pool, Some(&args.from)).await?;
+    let to = resolve_account(&pool, Some(&args.to)).await?;
+    if from == to {
+        return Err("can not transfer to the same account".into());
+    }
+    let occurred_at = match args.date {
+        Some(ref date) => parse_occurred_at(date)?,
+        None => Utc::now(),
+    };
+    let append_msg = args.add_msg.clone().unwrap_or(format!(
+        "transfer {} -> {}",
+        args.from.trim(),
+        args.to.trim()
+    ));
+
+    let mut tx = pool.begin().await?;
+    let transfer_id = sqlx::query("INSERT INTO transfer (created_at) VALUES (?)")
+        .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+
+    let query = r#"
+        INSERT INTO amount_record (amount, in_or_out, append_msg, account_id, transfer_id, occurred_at, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+    "#;
+    let mut record_ids = Vec::new();
+    for (account_id, in_or_out) in [(from, false), (to, true)] {
+        let record_id = sqlx::query(query)
+            .bind(args.amount)
+            .bind(in_or_out)
+            .bind(&append_msg)
+            .bind(account_id)
+            .bind(transfer_id)
+            .bind(occurred_at.format("%Y-%m-%d %H:%M:%S").to_string())
+            .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+            .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+            .execute(&mut *tx)
+            .await?
+            .last_insert_rowid();
+        record_ids.push(record_id);
+    }
+    journal(&mut tx, "transfer", inserted(&record_ids)).await?;
+    tx.commit().await?;
+
+    print_notice(format!(
+        "{}{}{}{}{}: {}",
+        "Transferred ".yellow().bold(),
+        args.from.trim().purple().bold(),
+        " -> ".yellow().bold(),
+        args.to.trim().purple().bold(),
+        " with amount".yellow().bold(),
+        args.amount.to_string().purple().bold()
+    ));
+    if is_machine_output() {
+        let records = select_records_in(&pool, "id", &record_ids).await?;
+        print_records(&export_records(&pool, &records).await?)?;
+    }
+
+    Ok(())
+}
+
+/// select the record with `id`,
+/// along with the other side if it is part of a transfer.
+async fn select_record_unit(pool: &SqlitePool, id: i32) -> Result<Vec<Amount>, Box<dyn Error>> {
+    let query_select = format!(
+        r#"{}
+        WHERE
+            amount_record.id = ?
+            OR amount_record.transfer_id = (SELECT transfer_id FROM amount_record WHERE id = ?)
+        ORDER BY amount_record.id"#,
+        AMOUNT_SELECT
     );
 
+    Ok(sqlx::query_as::<_, Amount>(&query_select)
+        .bind(id)
+        .bind(id)
+        .fetch_all(pool)
+        .await?)
+}
+
+/// select the records with `column` in `values`, in the order they happened.
+/// the records in the trash are selected too.
+pub async fn select_records_in(
+    pool: &SqlitePool,
+    column: &str,
+    values: &[i64],
+) -> Result<Vec<Amount>, Box<dyn Error>> {
+    let mut query = QueryBuilder::<Sqlite>::new(AMOUNT_SELECT);
+    query.push(format!(" WHERE amount_record.{} IN (", column));
+    let mut separated = query.separated(", ");
+    for x in values {
+        separated.push_bind(*x);
+    }
+    query.push(") ORDER BY occurred_at, amount_record.id");
+
+    Ok(query.build_query_as().fetch_all(pool).await?)
+}
+
+/// select the records by their ids and the filters,
+/// along with the other side of every transfer among them.
+/// returns the records and the ids which are selected themselves.
+async fn select_record_units(
+    pool: &SqlitePool,
+    ids: &[IdRange],
+    select: &RecordSelectArgs,
+) -> Result<(Vec<Amount>, HashSet<i32>), Box<dyn Error>> {
+    if ids.is_empty() && select.is_empty() {
+        return Err("give the ids of the records, or filters like --match or --last-month".into());
+    }
+    let pattern = match select.pattern {
+        Some(ref pattern) => {
+            Some(Regex::new(pattern).map_err(|e| format!("invalid --match regex: {}", e))?)
+        }
+        None => None,
+    };
+
+    let mut query = QueryBuilder::<Sqlite>::new(AMOUNT_SELECT);
+    query.push(" WHERE amount_record.deleted_at IS NULL");
+    if !ids.is_empty() {
+        query.push(" AND (");
+        let mut separated = query.separated(" OR ");
+        for x in ids {
+            separated
+                .push("amount_record.id BETWEEN ")
+                .push_bind_unseparated(x.start)
+                .push_unseparated(" AND ")
+                .push_bind_unseparated(x.end);
+        }
+        query.push(")");
+    }
+    push_date_range(&mut query, "occurred_at", &select.range.range()?);
+    if let Some(min) = select.min {
+        query.push(" AND amount >= ").push_bind(min);
+    }
+    if let Some(max) = select.max {
+        query.push(" AND amount <= ").push_bind(max);
+    }
+    query.push(" ORDER BY occurred_at, amount_record.id");
+
+    let selected: Vec<Amount> = query
+        .build_query_as::<Amount>()
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .filter(|x| pattern.as_ref().is_none_or(|p| p.is_match(&x.append_msg)))
+        .collect();
+    if selected.is_empty() {
+        return Err("no record matches the ids and filters".into());
+    }
+    let selected_ids: HashSet<i32> = selected.iter().map(|x| x.id).collect();
+
+    let transfer_ids: Vec<i64> = selected.iter().filter_map(|x| x.transfer_id).collect();
+    if transfer_ids.is_empty() {
+        return Ok((selected, selected_ids));
+    }
+    let mut records = selected;
+    records.extend(
+        select_records_in(pool, "transfer_id", &transfer_ids)
+            .await?
+            .into_iter()
+            .filter(|x| !selected_ids.contains(&x.id)),
+    );
+    records.sort_by_key(|x| (x.occurred_at, x.id));
+    Ok((records, selected_ids))
+}
+
+/// use tabled to format the records, a table for each month.
+fn format_output_months(records: &Vec<Amount>) -> Result<(), Box<dyn Error>> {
+    let grouped_amount = group_amount_by_month(records);
+    let mut sorted_months: Vec<String> = grouped_amount.keys().cloned().collect();
+    sorted_months.sort_unstable();
+
+    for month in sorted_months {
+        format_output_month_total(&grouped_amount[&month], month.clone(), &[])?;
+    }
     Ok(())
 }
 
-/// Delete the specified record.
+/// Move the selected records to the trash.
 pub async fn delete_record(
     args: &DeleteRecordArgs,
     database_path: PathBuf,
 ) -> Result<(), Box<dyn Error>> {
+    check_confirm(args.yes, args.dry_run)?;
     let options = SqliteConnectOptions::new().filename(&database_path);
     let pool = SqlitePool::connect_with(options).await?;
 
-    let query_select = r#"
-        SELECT 
-            id, amount, in_or_out, append_msg, created_at, updated_at
-        FROM 
-            amount_record
-        WHERE 
-            id = ?
-    "#;
-
-    let record = sqlx::query_as::<_, Amount>(query_select)
-        .bind(args.id)
-        .fetch_all(&pool)
-        .await
-        .expect("error when select the amount_record");
-
-    assert!(record.len() == 1, "the id is not exist");
-    format_output_month_total(&record, record[0].created_at.to_string()).expect("format error");
-    println!(
-        "{}{}{}{}",
-        "Deleted ".red().bold(),
-        "record with id ".yellow().bold(),
-        args.id.to_string().purple().bold(),
-        ". Input Yes(YES/yes/Y/y) to confirm, other to give up."
-            .blue()
-            .bold()
-    );
-    let mut input = String::new();
-    std::io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
+    let (record, _) = select_record_units(&pool, &args.ids, &args.select).await?;
 
-    if !["yes", "y"].contains(&input.trim().to_lowercase().as_str()) {
-        println!("{}", "Give up".red().bold());
+    format_output_months(&record)?;
+    if args.dry_run {
+        print_notice("Dry run, nothing is deleted.".yellow().bold());
         return Ok(());
     }
+    if !args.yes
+        && !confirm(format!(
+            "{}{}{}",
+            "Delete ".red().bold(),
+            format!("{} records", record.len()).purple().bold(),
+            ". Input Yes(YES/yes/Y/y) to confirm, other to give up."
+                .blue()
+                .bold()
+        ))?
+    {
+        print_notice("Give up".red().bold());
+        return Ok(());
+    }
+    // the transfer account is gone after the deletion.
+    let deleted = export_records(&pool, &record).await?;
 
     let query = r#"
-        DELETE FROM amount_record
+        UPDATE amount_record
+        SET deleted_at = ?
         WHERE id = ?
     "#;
 
-    sqlx::query(query)
-        .bind(args.id)
-        .execute(&pool)
-        .await
-        .expect("error when delete the amount_record");
+    let ids: Vec<i64> = record.iter().map(|x| x.id as i64).collect();
+    let mut tx = pool.begin().await?;
+    let before = record_images(&mut tx, &ids).await?;
+    for x in &record {
+        sqlx::query(query)
+            .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+            .bind(x.id)
+            .execute(&mut *tx)
+            .await
+            .expect("error when delete the amount_record");
+    }
+    journal(&mut tx, "delete-record", before).await?;
+    tx.commit().await?;
 
-    println!("{}", "Delete successfully!".green().bold());
+    print_notice(format!(
+        "{}{}",
+        "Delete successfully! ".green().bold(),
+        "Restore the records with `rustance trash restore <id>`.".blue()
+    ));
+    print_records(&deleted)?;
 
     Ok(())
 }
 
+/// Patch the selected records.
 pub async fn patch_record(
     args: &PatchRecordArgs,
     database_path: PathBuf,
 ) -> Result<(), Box<dyn Error>> {
+    check_confirm(args.yes, args.dry_run)?;
     let options = SqliteConnectOptions::new().filename(&database_path);
     let pool = SqlitePool::connect_with(options).await?;
 
-    let query_select = r#"
-        SELECT 
-            id, amount, in_or_out, append_msg, created_at, updated_at
-        FROM 
-            amount_record
-        WHERE 
-            id = ?
-    "#;
+    let (record, selected_ids) = select_record_units(&pool, &args.ids, &args.select).await?;
 
-    let record = sqlx::query_as::<_, Amount>(query_select)
-        .bind(args.id)
-        .fetch_all(&pool)
-        .await
-        .expect("error when select the amount_record");
-
-    assert!(record.len() == 1, "the id is not exist");
-    println!("{}", "Before:".blue().bold());
-    format_output_month_total(&record, record[0].created_at.to_string()).expect("format error");
-    println!("{}", "Patched:".blue().bold());
-    let records_now = Amount {
-        id: args.id,
-        amount: args
-            .amount
-            .map_or(record[0].amount, |amount| (amount * 100.0) as u32),
-
-        in_or_out: args.in_or_out.unwrap_or(record[0].in_or_out),
-        append_msg: args.add_msg.clone().unwrap_or(record[0].append_msg.clone()),
-        created_at: record[0].created_at,
-        updated_at: Utc::now(),
+    let has_transfer = record.iter().any(|x| x.transfer_id.is_some());
+    if has_transfer && args.in_or_out.is_some() {
+        return Err("can not change the direction of a transfer".into());
+    }
+    if has_transfer && args.category.is_some() {
+        return Err("a transfer has no category".into());
+    }
+
+    print_notice("Before:".blue().bold());
+    format_output_months(&record)?;
+    print_notice("Patched:".blue().bold());
+    let occurred_at = match args.date {
+        Some(ref date) => Some(parse_occurred_at(date)?),
+        None => None,
+    };
+    let account_id = match args.account {
+        Some(ref account) => Some(resolve_account(&pool, Some(account)).await?),
+        None => None,
     };
+    // `Some(None)` clears the category.
+    let category = match args.category.as_deref().map(str::trim) {
+        Some("") => Some((None, None)),
+        Some(path) => Some((
+            Some(resolve_category(&pool, path).await?),
+            Some(path.to_string()),
+        )),
+        None => None,
+    };
+    let tags = parse_tags(args.add_msg.as_deref(), &args.tags)?;
+    let untags = parse_tags(None, &args.untags)?;
 
-    format_output_month_total(&[records_now.clone()], record[0].created_at.to_string())
-        .expect("format error");
-
-    println!(
-        "{}{}{}{}",
-        "Patch ".blue().bold(),
-        "record with id ".yellow().bold(),
-        args.id.to_string().purple().bold(),
-        ". Input Yes(YES/yes/Y/y) to confirm, other to give up."
-            .blue()
-            .bold()
-    );
-    let mut input = String::new();
-    std::io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
-    if !["yes", "y"].contains(&input.trim().to_lowercase().as_str()) {
-        println!("{}", "Give up".red().bold());
+    let records_now: Vec<Amount> = record
+        .iter()
+        .map(|x| {
+            // only the selected side of a transfer moves to the account.
+            let (account_id, account) = match account_id {
+                Some(account_id) if selected_ids.contains(&x.id) => (
+                    Some(account_id),
+                    args.account.clone().map(|a| a.trim().to_string()),
+                ),
+                _ => (x.account_id, x.account.clone()),
+            };
+            Amount {
+                id: x.id,
+                amount: args.amount.unwrap_or(x.amount),
+                in_or_out: args.in_or_out.unwrap_or(x.in_or_out),
+                append_msg: args.add_msg.clone().unwrap_or(x.append_msg.clone()),
+                account_id,
+                account,
+                transfer_id: x.transfer_id,
+                category_id: category.as_ref().map_or(x.category_id, |c| c.0),
+                category: category
+                    .as_ref()
+                    .map_or(x.category.clone(), |c| c.1.clone()),
+                tags: patch_tags(x.tags.as_deref(), &tags, &untags),
+                source: x.source.clone(),
+                external_id: x.external_id.clone(),
+                occurred_at: occurred_at.unwrap_or(x.occurred_at),
+                created_at: x.created_at,
+                updated_at: Utc::now(),
+                deleted_at: x.deleted_at,
+            }
+        })
+        .collect();
+    let mut transfer_accounts: HashMap<i64, Option<i64>> = HashMap::new();
+    for x in &records_now {
+        if let Some(transfer_id) = x.transfer_id
+            && transfer_accounts.insert(transfer_id, x.account_id) == Some(x.account_id)
+        {
+            return Err("can not transfer to the same account".into());
+        }
+    }
+
+    format_output_months(&records_now)?;
+
+    if args.dry_run {
+        print_notice("Dry run, nothing is patched.".yellow().bold());
+        return Ok(());
+    }
+    if !args.yes
+        && !confirm(format!(
+            "{}{}{}",
+            "Patch ".blue().bold(),
+            format!("{} records", records_now.len()).purple().bold(),
+            ". Input Yes(YES/yes/Y/y) to confirm, other to give up."
+                .blue()
+                .bold()
+        ))?
+    {
+        print_notice("Give up".red().bold());
         return Ok(());
     }
 
     let query = r#"
         UPDATE amount_record
-        SET amount = ?, in_or_out = ?, append_msg = ?, updated_at = ?
+        SET amount = ?, in_or_out = ?, append_msg = ?, account_id = ?, category_id = ?, occurred_at = ?, updated_at = ?
         WHERE id = ?
     "#;
 
-    let affect_line = sqlx::query(query)
-        .bind(records_now.amount)
-        .bind(records_now.in_or_out)
-        .bind(records_now.append_msg)
-        .bind(
-            records_now
-                .updated_at
-                .format("%Y-%m-%d %H:%M:%S")
-                .to_string(),
-        )
-        .bind(args.id)
-        .execute(&pool)
-        .await
-        .expect("unexpected error when patch the amount_record");
+    let ids: Vec<i64> = records_now.iter().map(|x| x.id as i64).collect();
+    let mut tx = pool.begin().await?;
+    let before = record_images(&mut tx, &ids).await?;
+    for x in &records_now {
+        let affect_line = sqlx::query(query)
+            .bind(x.amount)
+            .bind(x.in_or_out)
+            .bind(&x.append_msg)
+            .bind(x.account_id)
+            .bind(x.category_id)
+            .bind(x.occurred_at.format("%Y-%m-%d %H:%M:%S").to_string())
+            .bind(x.updated_at.format("%Y-%m-%d %H:%M:%S").to_string())
+            .bind(x.id)
+            .execute(&mut *tx)
+            .await
+            .expect("unexpected error when patch the amount_record");
 
-    assert!(affect_line.rows_affected() == 1, "patch error.");
-    println!("{}", "Patch successfully!".green().bold());
+        assert!(affect_line.rows_affected() == 1, "patch error.");
+        remove_record_tags(&mut tx, x.id as i64, &untags).await?;
+        add_record_tags(&mut tx, x.id as i64, &tags).await?;
+    }
+    journal(&mut tx, "patch-record", before).await?;
+    tx.commit().await?;
+    print_notice("Patch successfully!".green().bold());
+    if is_machine_output() {
+        let patched = select_records_in(&pool, "id", &ids).await?;
+        print_records(&export_records(&pool, &patched).await?)?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,427 @@
+use crate::account::resolve_account;
+use crate::category::resolve_category;
+use crate::cli_customs::{RecurringAddArgs, RecurringArgs, RecurringCommands, RecurringRemoveArgs};
+use crate::journal::{inserted, journal};
+use crate::money::Money;
+use crate::output::{print_notice, print_rows, print_table};
+use crate::tag::{add_record_tags, parse_tags};
+use crate::time_utils::{local_midnight, to_local};
+use chrono::{Duration, Months, NaiveDate, Utc};
+use colored::*;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use std::error::Error;
+use std::path::Path;
+use std::path::PathBuf;
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Panel,
+        object::Rows,
+        style::{Border, BorderColor, Style},
+        themes::Colorization,
+    },
+};
+
+/// a recurring record as it is stored.
+#[derive(FromRow, Clone, Debug)]
+pub struct Recurring {
+    pub id: i64,
+    pub amount: Money,
+    pub in_or_out: bool,
+    pub append_msg: String,
+    pub account_id: Option<i64>,
+    pub category_id: Option<i64>,
+    /// space separated tags.
+    pub tags: String,
+    /// one of `day`, `week`, `month` and `year`.
+    pub unit: String,
+    pub step: i64,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    /// how many occurrences are already added as records.
+    pub materialized: i64,
+}
+
+impl Recurring {
+    /// the date of the `n`th occurrence, counting from 0.
+    /// monthly occurrences past the end of a short month fall on its last day.
+    pub fn occurrence(&self, n: i64) -> Option<NaiveDate> {
+        let steps = self.step.checked_mul(n)?;
+        let date = match self.unit.as_str() {
+            "day" => self.start_date.checked_add_signed(Duration::days(steps))?,
+            "week" => self.start_date.checked_add_signed(Duration::weeks(steps))?,
+            "month" => self
+                .start_date
+                .checked_add_months(Months::new(u32::try_from(steps).ok()?))?,
+            "year" => self
+                .start_date
+                .checked_add_months(Months::new(u32::try_from(steps.checked_mul(12)?).ok()?))?,
+            _ => return None,
+        };
+        match self.end_date {
+            Some(end) if date > end => None,
+            _ => Some(date),
+        }
+    }
+
+    /// the due occurrences not yet added, with their index.
+    fn due(&self, until: NaiveDate) -> Vec<(i64, NaiveDate)> {
+        (self.materialized..)
+            .map_while(|n| self.occurrence(n).map(|date| (n, date)))
+            .take_while(|(_, date)| *date <= until)
+            .collect()
+    }
+
+    /// like `every 2 weeks from 2025-03-05`.
+    fn schedule(&self) -> String {
+        let every = if self.step == 1 {
+            self.unit.clone()
+        } else {
+            format!("{} {}s", self.step, self.unit)
+        };
+        match self.end_date {
+            Some(end) => format!("every {} from {} to {}", every, self.start_date, end),
+            None => format!("every {} from {}", every, self.start_date),
+        }
+    }
+}
+
+/// a row of the recurring list output.
+#[derive(Clone, Debug, Tabled, Serialize)]
+struct RecurringRow {
+    id: i64,
+    amount: Money,
+    in_or_out: bool,
+    append_msg: String,
+    schedule: String,
+    next: String,
+}
+
+/// a row of the records a run adds.
+#[derive(Clone, Debug, Tabled, Serialize)]
+struct DueRow {
+    recurring: i64,
+    amount: Money,
+    in_or_out: bool,
+    append_msg: String,
+    date: String,
+}
+
+/// parse `--every` like `month` or `2 weeks` into its unit and step.
+fn parse_every(every: &str) -> Result<(String, i64), String> {
+    let words: Vec<&str> = every.split_whitespace().collect();
+    let (step, unit) = match words.as_slice() {
+        [unit] => (1, *unit),
+        [step, unit] => (
+            step.parse::<i64>()
+                .map_err(|_| format!("invalid recurrence `{}`", every))?,
+            *unit,
+        ),
+        _ => return Err(format!("invalid recurrence `{}`", every)),
+    };
+    let unit = unit.to_lowercase();
+    let unit = unit.trim_end_matches('s');
+    let unit = match unit {
+        "day" | "daily" => "day",
+        "week" | "weekly" => "week",
+        "month" | "monthly" => "month",
+        "year" | "yearly" => "year",
+        _ => {
+            return Err(format!(
+                "invalid recurrence `{}`, expected day, week, month or year",
+                every
+            ));
+        }
+    };
+    if step < 1 {
+        return Err(format!(
+            "invalid recurrence `{}`, must be at least 1",
+            every
+        ));
+    }
+    Ok((unit.to_string(), step))
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("invalid date `{}`, expected YYYY-MM-DD", date))
+}
+
+/// dispatch the `recurring` subcommands.
+pub async fn recurring_command(
+    args: &RecurringArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    match &args.command {
+        RecurringCommands::Add(arg) => add_recurring(arg, &pool).await,
+        RecurringCommands::List => list_recurring(&pool).await,
+        RecurringCommands::Remove(arg) => remove_recurring(arg, &pool).await,
+        RecurringCommands::Run(arg) => {
+            let until = match arg.until {
+                Some(ref until) => parse_date(until)?,
+                None => to_local(Utc::now()).date_naive(),
+            };
+            let due = run_recurring(&pool, until, arg.dry_run).await?;
+            print_rows(&due)?;
+            Ok(())
+        }
+    }
+}
+
+async fn add_recurring(args: &RecurringAddArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let (unit, step) = parse_every(&args.every)?;
+    let start_date = match args.start {
+        Some(ref start) => parse_date(start)?,
+        None => to_local(Utc::now()).date_naive(),
+    };
+    let end_date = match args.end {
+        Some(ref end) => Some(parse_date(end)?),
+        None => None,
+    };
+    if end_date.is_some_and(|end| end < start_date) {
+        return Err("the end date is before the start date".into());
+    }
+    let account_id = resolve_account(pool, args.account.as_deref()).await?;
+    let category_id = match args.category {
+        Some(ref category) => Some(resolve_category(pool, category).await?),
+        None => None,
+    };
+    let tags = parse_tags(args.add_msg.as_deref(), &args.tags)?;
+
+    let query = r#"
+        INSERT INTO recurring (amount, in_or_out, append_msg, account_id, category_id, tags, unit, step, start_date, end_date, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    "#;
+    let id = sqlx::query(query)
+        .bind(args.amount)
+        .bind(args.income)
+        .bind(args.add_msg.clone().unwrap_or("".to_string()))
+        .bind(account_id)
+        .bind(category_id)
+        .bind(tags.join(" "))
+        .bind(&unit)
+        .bind(step)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+        .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+        .execute(pool)
+        .await?
+        .last_insert_rowid();
+
+    print_notice(format!(
+        "{}{}{}: {}",
+        "Added recurring ".yellow().bold(),
+        if args.income {
+            "income ".green().bold()
+        } else {
+            "outcome ".red().bold()
+        },
+        format!("#{} with amount", id).yellow().bold(),
+        args.amount.to_string().purple().bold()
+    ));
+    Ok(())
+}
+
+async fn select_recurring(pool: &SqlitePool) -> Result<Vec<Recurring>, Box<dyn Error>> {
+    let query = r#"
+        SELECT
+            id, amount, in_or_out, append_msg, account_id, category_id, tags,
+            unit, step, start_date, end_date, materialized
+        FROM
+            recurring
+        ORDER BY
+            id
+    "#;
+    Ok(sqlx::query_as(query).fetch_all(pool).await?)
+}
+
+async fn list_recurring(pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let rules = select_recurring(pool).await?;
+    let rows: Vec<RecurringRow> = rules
+        .iter()
+        .map(|x| RecurringRow {
+            id: x.id,
+            amount: x.amount,
+            in_or_out: x.in_or_out,
+            append_msg: x.append_msg.clone(),
+            schedule: x.schedule(),
+            next: x
+                .occurrence(x.materialized)
+                .map_or("finished".to_string(), |date| date.to_string()),
+        })
+        .collect();
+    if print_rows(&rows)? {
+        return Ok(());
+    }
+    if rows.is_empty() {
+        print_notice(
+            "No recurring record yet, add one with `rustance recurring add`."
+                .yellow()
+                .bold(),
+        );
+        return Ok(());
+    }
+
+    let table = Table::new(rows)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_BLUE,
+            Color::FG_BRIGHT_RED,
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_BRIGHT_YELLOW,
+            Color::FG_MAGENTA,
+        ]))
+        .with(Panel::header("Recurring Records\n"))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .to_string();
+    print_table(table);
+
+    Ok(())
+}
+
+async fn remove_recurring(
+    args: &RecurringRemoveArgs,
+    pool: &SqlitePool,
+) -> Result<(), Box<dyn Error>> {
+    let affect_line = sqlx::query("DELETE FROM recurring WHERE id = ?")
+        .bind(args.id)
+        .execute(pool)
+        .await?;
+    if affect_line.rows_affected() == 0 {
+        return Err(format!("recurring record #{} does not exist", args.id).into());
+    }
+
+    print_notice(format!(
+        "{}{}",
+        "Removed recurring ".yellow().bold(),
+        format!("#{}", args.id).purple().bold()
+    ));
+    Ok(())
+}
+
+/// add the records of every recurring record due until `until`, each exactly once.
+/// with `dry_run`, only show them. returns the due rows.
+async fn run_recurring(
+    pool: &SqlitePool,
+    until: NaiveDate,
+    dry_run: bool,
+) -> Result<Vec<DueRow>, Box<dyn Error>> {
+    let rules = select_recurring(pool).await?;
+    let due: Vec<DueRow> = rules
+        .iter()
+        .flat_map(|x| {
+            x.due(until).into_iter().map(|(_, date)| DueRow {
+                recurring: x.id,
+                amount: x.amount,
+                in_or_out: x.in_or_out,
+                append_msg: x.append_msg.clone(),
+                date: date.to_string(),
+            })
+        })
+        .collect();
+    if due.is_empty() {
+        if dry_run {
+            print_notice("No recurring record is due.".yellow().bold());
+        }
+        return Ok(due);
+    }
+
+    let table = Table::new(&due)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_BLUE,
+            Color::FG_BRIGHT_RED,
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_MAGENTA,
+        ]))
+        .with(Panel::header(if dry_run {
+            "Recurring Records Due (dry run)\n"
+        } else {
+            "Recurring Records Added\n"
+        }))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .to_string();
+    print_table(table);
+
+    if dry_run {
+        return Ok(due);
+    }
+
+    let query = r#"
+        INSERT INTO amount_record (amount, in_or_out, append_msg, account_id, category_id, occurred_at, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+    "#;
+    let mut tx = pool.begin().await?;
+    let mut record_ids = Vec::new();
+    for rule in &rules {
+        let occurrences = rule.due(until);
+        let Some((last, _)) = occurrences.last() else {
+            continue;
+        };
+        let tags: Vec<String> = rule.tags.split_whitespace().map(str::to_string).collect();
+        for (_, date) in &occurrences {
+            let record_id = sqlx::query(query)
+                .bind(rule.amount)
+                .bind(rule.in_or_out)
+                .bind(&rule.append_msg)
+                .bind(rule.account_id)
+                .bind(rule.category_id)
+                .bind(
+                    local_midnight(*date)
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string(),
+                )
+                .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+                .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+                .execute(&mut *tx)
+                .await?
+                .last_insert_rowid();
+            add_record_tags(&mut tx, record_id, &tags).await?;
+            record_ids.push(record_id);
+        }
+        // only move on if nobody else ran it meanwhile, so each occurrence is added once.
+        let affect_line = sqlx::query(
+            "UPDATE recurring SET materialized = ?, updated_at = ? WHERE id = ? AND materialized = ?",
+        )
+        .bind(last + 1)
+        .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+        .bind(rule.id)
+        .bind(rule.materialized)
+        .execute(&mut *tx)
+        .await?;
+        if affect_line.rows_affected() != 1 {
+            return Err("recurring records changed during the run, try again".into());
+        }
+    }
+    journal(&mut tx, "recurring run", inserted(&record_ids)).await?;
+    tx.commit().await?;
+
+    print_notice(format!(
+        "{}{}",
+        "Added recurring records: ".green().bold(),
+        due.len().to_string().purple().bold()
+    ));
+    Ok(due)
+}
+
+/// add the due recurring records before a command reading or writing records.
+pub async fn run_recurring_on_start(database_path: &Path) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+    run_recurring(&pool, to_local(Utc::now()).date_naive(), false).await?;
+    Ok(())
+}
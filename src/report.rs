@@ -0,0 +1,292 @@
+use crate::cli_customs::{OutputFormat, ReportArgs, ReportCommands, ReportYearArgs};
+use crate::html::html_report;
+use crate::money::Money;
+use crate::output::{output_format, print_csv, print_json, print_table};
+use crate::process::{Amount, select_range_records};
+use crate::time_utils::{DateRange, local_today, to_local};
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use std::error::Error;
+use std::path::PathBuf;
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Panel,
+        object::Rows,
+        style::{Border, BorderColor, Style},
+        themes::Colorization,
+    },
+};
+
+/// the totals of a month, or of a whole year.
+/// transfers only move money between accounts and are left out.
+#[derive(Clone, Debug, Tabled, Serialize)]
+pub struct PeriodSummary {
+    pub period: String,
+    pub income: Money,
+    pub outcome: Money,
+    pub net: Money,
+    /// the part of the income which is not spent, empty without income.
+    pub savings_rate: String,
+    pub records: i64,
+}
+
+/// the months of a year with its totals and averages.
+#[derive(Clone, Debug, Serialize)]
+pub struct YearSummary {
+    pub year: i32,
+    pub months: Vec<PeriodSummary>,
+    pub total: PeriodSummary,
+    /// the average of the months passed.
+    pub average: PeriodSummary,
+}
+
+/// the `report year` data of the json output.
+#[derive(Serialize, Debug)]
+struct YearReport {
+    year: YearSummary,
+    previous: Option<YearSummary>,
+}
+
+/// a month of the year compared with the same month of the year before.
+#[derive(Clone, Debug, Tabled)]
+struct CompareRow {
+    period: String,
+    last_outcome: Money,
+    outcome: Money,
+    outcome_change: String,
+    last_net: Money,
+    net: Money,
+}
+
+/// the change from `last` to `now` in percent, empty if `last` is zero.
+fn change_text(last: Money, now: Money) -> String {
+    match last.minor() {
+        0 => String::new(),
+        x => format!("{:+.1}%", (now.minor() - x) as f64 * 100.0 / x.abs() as f64),
+    }
+}
+
+impl PeriodSummary {
+    /// the totals of `records`.
+    pub fn of<'a>(
+        period: String,
+        records: impl IntoIterator<Item = &'a Amount>,
+    ) -> Result<Self, String> {
+        let (mut income, mut outcome, mut count) = (Money::ZERO, Money::ZERO, 0);
+        for x in records.into_iter().filter(|x| x.transfer_id.is_none()) {
+            match x.in_or_out {
+                true => income = income.checked_add(x.amount)?,
+                false => outcome = outcome.checked_add(x.amount)?,
+            }
+            count += 1;
+        }
+        Ok(PeriodSummary::new(period, income, outcome, count))
+    }
+
+    /// the totals of the summaries of shorter periods.
+    fn sum<'a>(
+        period: String,
+        summaries: impl IntoIterator<Item = &'a PeriodSummary>,
+    ) -> Result<Self, String> {
+        let (mut income, mut outcome, mut count) = (Money::ZERO, Money::ZERO, 0);
+        for x in summaries {
+            income = income.checked_add(x.income)?;
+            outcome = outcome.checked_add(x.outcome)?;
+            count += x.records;
+        }
+        Ok(PeriodSummary::new(period, income, outcome, count))
+    }
+
+    fn new(period: String, income: Money, outcome: Money, records: i64) -> Self {
+        let net = income - outcome;
+        let savings_rate = match income.minor() {
+            0 => String::new(),
+            x => format!("{:.1}%", net.minor() as f64 * 100.0 / x as f64),
+        };
+        PeriodSummary {
+            period,
+            income,
+            outcome,
+            net,
+            savings_rate,
+            records,
+        }
+    }
+}
+
+/// the months of `year` passed, the months after this one are not counted.
+fn months_passed(year: i32) -> usize {
+    let today = local_today();
+    match year == today.year() {
+        true => today.month() as usize,
+        false => 12,
+    }
+}
+
+/// the summary of every month of `year`.
+pub async fn year_summary(pool: &SqlitePool, year: i32) -> Result<YearSummary, Box<dyn Error>> {
+    let first = NaiveDate::from_ymd_opt(year, 1, 1).ok_or(format!("invalid year `{}`", year))?;
+    let records = select_range_records(pool, &DateRange::year(first)?).await?;
+
+    let months: Vec<PeriodSummary> = (1..=12)
+        .map(|month| {
+            PeriodSummary::of(
+                format!("{}-{:02}", year, month),
+                records
+                    .iter()
+                    .filter(|x| to_local(x.occurred_at).month() == month),
+            )
+        })
+        .collect::<Result<_, _>>()?;
+    let total = PeriodSummary::of(format!("{} total", year), &records)?;
+
+    let passed = months_passed(year) as i64;
+    let average_of = |x: Money| Money::from_minor(x.minor() / passed);
+    let average = PeriodSummary::new(
+        format!("{} average", year),
+        average_of(total.income),
+        average_of(total.outcome),
+        total.records / passed,
+    );
+
+    Ok(YearSummary {
+        year,
+        months,
+        total,
+        average,
+    })
+}
+
+/// dispatch the `report` subcommands.
+pub async fn report_command(
+    args: &ReportArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    match &args.command {
+        ReportCommands::Year(arg) => year_report(arg, &pool).await,
+        ReportCommands::Html(arg) => html_report(arg, &pool).await,
+    }
+}
+
+/// the overview of a year month by month, with the year before by `--compare`.
+async fn year_report(args: &ReportYearArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let year = args.year.unwrap_or(local_today().year());
+    let summary = year_summary(pool, year).await?;
+    let previous = match args.compare {
+        true => Some(year_summary(pool, year - 1).await?),
+        false => None,
+    };
+
+    match output_format() {
+        OutputFormat::Json => {
+            return print_json(&YearReport {
+                year: summary,
+                previous,
+            });
+        }
+        OutputFormat::Csv => {
+            let rows: Vec<PeriodSummary> = previous
+                .into_iter()
+                .chain([summary])
+                .flat_map(|x| x.months.into_iter().chain([x.total, x.average]))
+                .collect();
+            return print_csv(&rows);
+        }
+        OutputFormat::Table | OutputFormat::Plain => {}
+    }
+
+    let mut rows = summary.months.clone();
+    rows.extend([summary.total.clone(), summary.average.clone()]);
+    // the panel, the header and the twelve months come before the totals.
+    let total_row = 14;
+    let table = Table::new(rows)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_GREEN,
+            Color::FG_BRIGHT_RED,
+            Color::FG_BRIGHT_BLUE,
+            Color::FG_BRIGHT_YELLOW,
+            Color::FG_MAGENTA,
+        ]))
+        .with(Panel::header(format!("Year {}\n", year)))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .with(
+            Highlight::new(Rows::single(total_row))
+                .color(BorderColor::default().top(Color::FG_YELLOW)),
+        )
+        .with(Highlight::new(Rows::single(total_row)).border(Border::new().top('━')))
+        .to_string();
+    print_table(table);
+
+    let Some(previous) = previous else {
+        return Ok(());
+    };
+    // a year still going is compared with the same months of the year before.
+    let passed = months_passed(year);
+    let (last_total, total) = match passed {
+        12 => (previous.total.clone(), summary.total.clone()),
+        x => (
+            PeriodSummary::sum(String::new(), &previous.months[..x])?,
+            PeriodSummary {
+                period: format!("{}-01 ~ {}-{:02}", year, year, x),
+                ..summary.total.clone()
+            },
+        ),
+    };
+    let rows: Vec<CompareRow> = previous
+        .months
+        .iter()
+        .chain([&last_total])
+        .zip(summary.months.iter().chain([&total]))
+        .enumerate()
+        .map(|(i, (last, now))| CompareRow {
+            period: now.period.clone(),
+            last_outcome: last.outcome,
+            outcome: now.outcome,
+            // the months to come have no change yet.
+            outcome_change: match (passed..12).contains(&i) {
+                true => String::new(),
+                false => change_text(last.outcome, now.outcome),
+            },
+            last_net: last.net,
+            net: now.net,
+        })
+        .collect();
+    let table = Table::new(rows)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_RED,
+            Color::FG_BRIGHT_RED,
+            Color::FG_BRIGHT_YELLOW,
+            Color::FG_BLUE,
+            Color::FG_BRIGHT_BLUE,
+        ]))
+        .with(Panel::header(format!(
+            "Year {} Compared With {}\n",
+            year,
+            year - 1
+        )))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .with(
+            Highlight::new(Rows::single(total_row))
+                .color(BorderColor::default().top(Color::FG_YELLOW)),
+        )
+        .with(Highlight::new(Rows::single(total_row)).border(Border::new().top('━')))
+        .to_string();
+    print_table(table);
+
+    Ok(())
+}
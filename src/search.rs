@@ -0,0 +1,231 @@
+use crate::cli_customs::{OutputFormat, SearchArgs};
+use crate::export::export_records;
+use crate::output::{colored_output, output_format, print_csv, print_json, print_notice};
+use crate::process::{
+    AMOUNT_SELECT, Amount, format_output_month_total, group_amount_by_month, push_date_range,
+};
+use colored::*;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool, sqlite::SqliteConnectOptions};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// the ansi codes around a match, bold and underlined,
+/// so the color of the column goes on after it.
+const MATCH_START: &str = "\x1b[1;4m";
+const MATCH_END: &str = "\x1b[22;24m";
+
+/// the error of sqlite for a query fts5 can not parse, in the words of the command.
+fn query_error(error: sqlx::Error, query: &str) -> Box<dyn Error> {
+    match error {
+        sqlx::Error::Database(ref e) => format!(
+            "invalid search query `{}`: {}, quote a phrase like '\"coffee beans\"'",
+            query,
+            e.message()
+        )
+        .into(),
+        e => e.into(),
+    }
+}
+
+/// the words of a query without ascii words, like `咖啡`, to look up as substrings.
+/// the index keeps a run of CJK characters as one token, so a part of it never matches.
+fn substring_terms(query: &str) -> Option<Vec<String>> {
+    if query.chars().any(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some(
+        query
+            .split_whitespace()
+            .map(|x| x.trim_matches('"').to_string())
+            .filter(|x| !x.is_empty())
+            .collect(),
+    )
+}
+
+/// mark every place of `terms` in `msg` like the `highlight` of the index.
+fn highlight_terms(msg: &str, terms: &[String]) -> String {
+    let mut marked = vec![false; msg.len()];
+    for term in terms {
+        for (i, _) in msg.match_indices(term.as_str()) {
+            marked[i..i + term.len()].fill(true);
+        }
+    }
+    let (mut highlighted, mut inside) = (String::new(), false);
+    for (i, c) in msg.char_indices() {
+        if marked[i] != inside {
+            inside = marked[i];
+            highlighted.push_str(if inside { MATCH_START } else { MATCH_END });
+        }
+        highlighted.push(c);
+    }
+    if inside {
+        highlighted.push_str(MATCH_END);
+    }
+    highlighted
+}
+
+/// the records in the trash left out, whose messages match the query of `args`.
+pub async fn search_records(
+    pool: &SqlitePool,
+    args: &SearchArgs,
+) -> Result<Vec<Amount>, Box<dyn Error>> {
+    let mut query = QueryBuilder::<Sqlite>::new(AMOUNT_SELECT);
+    query.push(" WHERE amount_record.deleted_at IS NULL");
+    match substring_terms(&args.query) {
+        Some(terms) if terms.is_empty() => {
+            return Err(format!("invalid search query `{}`, it has no word", args.query).into());
+        }
+        Some(terms) => {
+            for x in terms {
+                let escaped = x
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                query
+                    .push(" AND append_msg LIKE ")
+                    .push_bind(format!("%{}%", escaped))
+                    .push(" ESCAPE '\\'");
+            }
+        }
+        None => {
+            query
+                .push(" AND amount_record.id IN (SELECT rowid FROM record_search WHERE record_search MATCH ")
+                .push_bind(&args.query)
+                .push(")");
+        }
+    }
+    push_date_range(&mut query, "occurred_at", &args.range.range()?);
+    if let Some(min) = args.min {
+        query.push(" AND amount >= ").push_bind(min);
+    }
+    if let Some(max) = args.max {
+        query.push(" AND amount <= ").push_bind(max);
+    }
+    query.push(" ORDER BY occurred_at, amount_record.id");
+    let records = query
+        .build_query_as()
+        .fetch_all(pool)
+        .await
+        .map_err(|e| query_error(e, &args.query))?;
+    Ok(records)
+}
+
+/// search the messages of the records with the full-text index,
+/// shown in the tables of `list-all` with the matches highlighted.
+pub async fn search_command(
+    args: &SearchArgs,
+    database_path: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+    let mut records = search_records(&pool, args).await?;
+
+    match output_format() {
+        OutputFormat::Json => return print_json(&export_records(&pool, &records).await?),
+        OutputFormat::Csv => return print_csv(&export_records(&pool, &records).await?),
+        OutputFormat::Table | OutputFormat::Plain => {}
+    }
+    if records.is_empty() {
+        print_notice("No record matches the search.".yellow().bold());
+        return Ok(());
+    }
+
+    if let Some(terms) = substring_terms(&args.query).filter(|_| colored_output()) {
+        for x in records.iter_mut() {
+            x.append_msg = highlight_terms(&x.append_msg, &terms);
+        }
+    } else if colored_output() {
+        let highlights: HashMap<i32, String> = sqlx::query_as(
+            "SELECT rowid, highlight(record_search, 0, ?, ?) FROM record_search WHERE record_search MATCH ?",
+        )
+        .bind(MATCH_START)
+        .bind(MATCH_END)
+        .bind(&args.query)
+        .fetch_all(&pool)
+        .await?
+        .into_iter()
+        .collect();
+        for x in records.iter_mut() {
+            if let Some(highlight) = highlights.get(&x.id) {
+                x.append_msg = highlight.clone();
+            }
+        }
+    }
+
+    let grouped_amount = group_amount_by_month(&records);
+    let mut sorted_months: Vec<String> = grouped_amount.keys().cloned().collect();
+    sorted_months.sort_unstable();
+    for month in sorted_months {
+        format_output_month_total(&grouped_amount[&month], month, &[])?;
+    }
+    print_notice(format!(
+        "{}{}",
+        "Matched records: ".blue().bold(),
+        records.len().to_string().purple().bold()
+    ));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_customs::AmountArgs;
+    use crate::database::test_database;
+    use crate::process::insert_in_or_out_come;
+
+    async fn search(pool: &SqlitePool, query: &str) -> Vec<String> {
+        let args = SearchArgs {
+            query: query.to_string(),
+            range: Default::default(),
+            min: None,
+            max: None,
+        };
+        search_records(pool, &args)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|x| x.append_msg)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn search_finds_a_part_of_chinese_messages() {
+        let database = test_database("search-cjk").await;
+        for msg in ["星巴克咖啡 latte", "午饭 noodles", "100% 咖啡豆"] {
+            let args = AmountArgs {
+                amount: "5".parse().unwrap(),
+                add_msg: Some(msg.to_string()),
+                date: None,
+                account: None,
+                category: None,
+                tags: Vec::new(),
+            };
+            insert_in_or_out_come(&args, database.clone(), false)
+                .await
+                .unwrap();
+        }
+        let pool = SqlitePool::connect_with(SqliteConnectOptions::new().filename(&database))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            search(&pool, "咖啡").await,
+            ["星巴克咖啡 latte", "100% 咖啡豆"]
+        );
+        assert_eq!(search(&pool, "星巴克 咖啡").await, ["星巴克咖啡 latte"]);
+        assert_eq!(search(&pool, "%").await, ["100% 咖啡豆"]);
+        assert_eq!(search(&pool, "latte").await, ["星巴克咖啡 latte"]);
+        assert!(search(&pool, "茶").await.is_empty());
+    }
+
+    #[test]
+    fn highlight_terms_marks_every_place() {
+        let terms = vec!["咖啡".to_string()];
+        assert_eq!(
+            highlight_terms("咖啡 and 咖啡豆", &terms),
+            format!("{0}咖啡{1} and {0}咖啡{1}豆", MATCH_START, MATCH_END)
+        );
+    }
+}
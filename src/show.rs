@@ -0,0 +1,256 @@
+use crate::cli_customs::{OutputFormat, ShowArgs};
+use crate::journal::{RecordImage, Revision, record_image, record_revisions};
+use crate::money::Money;
+use crate::output::{output_format, print_csv, print_json, print_notice, print_table};
+use crate::time_utils::to_local;
+use chrono::NaiveDateTime;
+use colored::*;
+use serde::Serialize;
+use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Modify, Panel, Width,
+        object::{Rows, Segment},
+        style::{Border, BorderColor, Style},
+        themes::Colorization,
+    },
+};
+
+/// the names of the accounts, categories and tags, to show the ids of an image.
+struct Names {
+    accounts: HashMap<i64, String>,
+    categories: HashMap<i64, String>,
+    tags: HashMap<i64, String>,
+}
+
+/// a field of the record and its value.
+#[derive(Clone, Debug, Tabled, Serialize)]
+struct FieldRow {
+    field: String,
+    value: String,
+}
+
+/// a changed field of a revision.
+#[derive(Clone, Debug, Tabled, Serialize)]
+struct ChangeRow {
+    /// the id of the operation.
+    revision: String,
+    changed_at: String,
+    author: String,
+    command: String,
+    field: String,
+    old: String,
+    new: String,
+}
+
+/// the `show` data of the json output.
+#[derive(Serialize, Debug)]
+struct ShowReport {
+    /// `None` if the record is purged.
+    record: Option<Vec<FieldRow>>,
+    history: Option<Vec<ChangeRow>>,
+}
+
+/// a utc time as it is stored, in local time.
+pub fn local_text(time: &str) -> String {
+    match NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S") {
+        Ok(x) => to_local(x.and_utc())
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        Err(_) => time.to_string(),
+    }
+}
+
+/// the fields of an image as they are shown, all empty for a record which does not exist.
+fn image_fields(image: Option<&RecordImage>, names: &Names) -> Vec<(&'static str, String)> {
+    let name = |map: &HashMap<i64, String>, id: Option<i64>| {
+        id.map(|x| map.get(&x).cloned().unwrap_or(format!("#{}", x)))
+            .unwrap_or_default()
+    };
+    let Some(x) = image else {
+        return [
+            "amount",
+            "in_or_out",
+            "append_msg",
+            "account",
+            "category",
+            "tags",
+            "occurred_at",
+            "source",
+            "external_id",
+            "deleted_at",
+        ]
+        .into_iter()
+        .map(|field| (field, String::new()))
+        .collect();
+    };
+    vec![
+        ("amount", Money::from_minor(x.amount).to_string()),
+        ("in_or_out", x.in_or_out.to_string()),
+        ("append_msg", x.append_msg.clone()),
+        ("account", name(&names.accounts, x.account_id)),
+        ("category", name(&names.categories, x.category_id)),
+        (
+            "tags",
+            x.tags
+                .iter()
+                .map(|id| name(&names.tags, Some(*id)))
+                .collect::<Vec<String>>()
+                .join(" "),
+        ),
+        ("occurred_at", local_text(&x.occurred_at)),
+        ("source", x.source.clone().unwrap_or_default()),
+        ("external_id", x.external_id.clone().unwrap_or_default()),
+        (
+            "deleted_at",
+            x.deleted_at.as_deref().map(local_text).unwrap_or_default(),
+        ),
+    ]
+}
+
+/// the changed fields of every revision, a row for each field.
+fn change_rows(revisions: &[Revision], names: &Names) -> Vec<ChangeRow> {
+    let mut rows = Vec::new();
+    for x in revisions {
+        let old = image_fields(x.before.as_ref(), names);
+        let new = image_fields(x.after.as_ref(), names);
+        let command = match (x.discarded, x.undone) {
+            (true, _) => format!("{} (discarded)", x.command),
+            (false, true) => format!("{} (undone)", x.command),
+            (false, false) => x.command.clone(),
+        };
+        let changed: Vec<_> = old
+            .into_iter()
+            .zip(new)
+            .filter(|(old, new)| old.1 != new.1)
+            .collect();
+        for (i, ((field, old), (_, new))) in changed.into_iter().enumerate() {
+            // the revision is only named on its first row.
+            let first = |text: String| if i == 0 { text } else { String::new() };
+            rows.push(ChangeRow {
+                revision: first(format!("#{}", x.operation_id)),
+                changed_at: first(local_text(&x.created_at)),
+                author: first(x.author.clone().unwrap_or_default()),
+                command: first(command.clone()),
+                field: field.to_string(),
+                old,
+                new,
+            });
+        }
+    }
+    rows
+}
+
+async fn load_names(pool: &SqlitePool) -> Result<Names, Box<dyn Error>> {
+    let map = |rows: Vec<(i64, String)>| rows.into_iter().collect::<HashMap<i64, String>>();
+    Ok(Names {
+        accounts: map(sqlx::query_as("SELECT id, name FROM account")
+            .fetch_all(pool)
+            .await?),
+        categories: map(sqlx::query_as("SELECT id, path FROM category_path")
+            .fetch_all(pool)
+            .await?),
+        tags: map(sqlx::query_as("SELECT id, name FROM tag")
+            .fetch_all(pool)
+            .await?),
+    })
+}
+
+/// show a record, with `--history` its revisions too.
+pub async fn show_command(args: &ShowArgs, database_path: PathBuf) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    let names = load_names(&pool).await?;
+    let image = record_image(&mut *pool.acquire().await?, args.id as i64).await?;
+    let revisions = match args.history {
+        true => Some(record_revisions(&pool, args.id as i64).await?),
+        false => None,
+    };
+    if image.is_none() && revisions.as_ref().is_none_or(|x| x.is_empty()) {
+        return Err(format!("record #{} does not exist", args.id).into());
+    }
+
+    let record: Option<Vec<FieldRow>> = image.as_ref().map(|x| {
+        image_fields(Some(x), &names)
+            .into_iter()
+            .map(|(field, value)| FieldRow {
+                field: field.to_string(),
+                value,
+            })
+            .collect()
+    });
+    let history = revisions.map(|x| change_rows(&x, &names));
+
+    match output_format() {
+        OutputFormat::Json => return print_json(&ShowReport { record, history }),
+        OutputFormat::Csv => {
+            return match history {
+                Some(ref history) => print_csv(history),
+                None => print_csv(record.as_deref().unwrap_or_default()),
+            };
+        }
+        OutputFormat::Table | OutputFormat::Plain => {}
+    }
+
+    match record {
+        Some(record) => {
+            let title = match image.as_ref().and_then(|x| x.deleted_at.as_ref()) {
+                Some(_) => format!("Record #{} (in the trash)\n", args.id),
+                None => format!("Record #{}\n", args.id),
+            };
+            let table = Table::new(record)
+                .with(Style::blank())
+                .with(Colorization::columns([
+                    Color::FG_BRIGHT_CYAN,
+                    Color::FG_BRIGHT_WHITE,
+                ]))
+                .with(Panel::header(title))
+                .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+                .modify(Rows::first(), Alignment::center())
+                .with(Modify::new(Segment::all()).with(Width::wrap(60)))
+                .with(
+                    Highlight::new(Rows::single(2))
+                        .color(BorderColor::default().top(Color::FG_YELLOW)),
+                )
+                .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+                .to_string();
+            print_table(table);
+        }
+        None => print_notice(format!("Record #{} is purged.", args.id).yellow().bold()),
+    }
+
+    let Some(history) = history else {
+        return Ok(());
+    };
+    if history.is_empty() {
+        print_notice("No change of the record is recorded.".yellow().bold());
+        return Ok(());
+    }
+    let table = Table::new(history)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_MAGENTA,
+            Color::FG_BRIGHT_GREEN,
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_BRIGHT_YELLOW,
+            Color::FG_BRIGHT_RED,
+            Color::FG_BRIGHT_GREEN,
+        ]))
+        .with(Panel::header(format!("History Of Record #{}\n", args.id)))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Modify::new(Segment::all()).with(Width::wrap(40)))
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .to_string();
+
+    print_table(table);
+
+    Ok(())
+}
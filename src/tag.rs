@@ -0,0 +1,289 @@
+use crate::cli_customs::{TagArgs, TagCommands, TagSummaryArgs};
+use crate::money::Money;
+use crate::output::{print_notice, print_rows, print_table};
+use crate::process::push_date_range;
+use crate::time_utils::DateRange;
+use chrono::Utc;
+use colored::*;
+use serde::Serialize;
+use sqlx::prelude::FromRow;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool, sqlite::SqliteConnectOptions};
+use std::error::Error;
+use std::path::PathBuf;
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Panel,
+        object::Rows,
+        style::{Border, BorderColor, Style},
+        themes::Colorization,
+    },
+};
+
+/// a tag with how many records it has.
+#[derive(FromRow, Clone, Debug, Tabled, Serialize)]
+struct TagUsage {
+    name: String,
+    records: i64,
+}
+
+/// the totals of a tag.
+#[derive(FromRow, Clone, Debug, Tabled, Serialize)]
+struct TagSummary {
+    name: String,
+    records: i64,
+    income: Money,
+    outcome: Money,
+    #[sqlx(skip)]
+    net: Money,
+}
+
+/// whether `c` can be part of a tag.
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+/// normalize a tag like `#Trip-Tokyo` into `trip-tokyo`.
+pub fn normalize_tag(raw: &str) -> Result<String, String> {
+    let tag = raw.trim().trim_start_matches('#').to_lowercase();
+    if tag.is_empty() || !tag.chars().all(is_tag_char) {
+        return Err(format!(
+            "invalid tag `{}`, only letters, digits, `-` and `_` are allowed",
+            raw
+        ));
+    }
+    Ok(tag)
+}
+
+/// the tags of a record, from the `--tag` options and the `#hashtags` in its message.
+pub fn parse_tags(msg: Option<&str>, explicit: &[String]) -> Result<Vec<String>, String> {
+    let mut tags = explicit
+        .iter()
+        .map(|x| normalize_tag(x))
+        .collect::<Result<Vec<String>, String>>()?;
+
+    for word in msg.unwrap_or("").split_whitespace() {
+        if let Some(rest) = word.strip_prefix('#') {
+            let tag: String = rest.chars().take_while(|c| is_tag_char(*c)).collect();
+            if !tag.is_empty() {
+                tags.push(tag.to_lowercase());
+            }
+        }
+    }
+
+    tags.sort_unstable();
+    tags.dedup();
+    Ok(tags)
+}
+
+/// add `tags` to a record, creating the tags that do not exist yet.
+pub async fn add_record_tags(
+    conn: &mut SqliteConnection,
+    record_id: i64,
+    tags: &[String],
+) -> Result<(), Box<dyn Error>> {
+    for tag in tags {
+        sqlx::query("INSERT OR IGNORE INTO tag (name, created_at) VALUES (?, ?)")
+            .bind(tag)
+            .bind(Utc::now().format("%Y-%m-%d %H:%M:%S").to_string())
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(
+            "INSERT OR IGNORE INTO record_tag (record_id, tag_id) SELECT ?, id FROM tag WHERE name = ?",
+        )
+        .bind(record_id)
+        .bind(tag)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// remove `tags` from a record.
+pub async fn remove_record_tags(
+    conn: &mut SqliteConnection,
+    record_id: i64,
+    tags: &[String],
+) -> Result<(), Box<dyn Error>> {
+    for tag in tags {
+        sqlx::query(
+            "DELETE FROM record_tag WHERE record_id = ? AND tag_id = (SELECT id FROM tag WHERE name = ?)",
+        )
+        .bind(record_id)
+        .bind(tag)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// dispatch the `tag` subcommands.
+pub async fn tag_command(args: &TagArgs, database_path: PathBuf) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    match &args.command {
+        TagCommands::List => list_tags(&pool).await,
+        TagCommands::Summary(arg) => tag_summary(arg, &pool).await,
+    }
+}
+
+async fn list_tags(pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let query = r#"
+        SELECT
+            tag.name, COUNT(amount_record.id) AS records
+        FROM
+            tag
+            LEFT JOIN record_tag ON record_tag.tag_id = tag.id
+            LEFT JOIN amount_record
+                ON amount_record.id = record_tag.record_id AND amount_record.deleted_at IS NULL
+        GROUP BY
+            tag.id
+        ORDER BY
+            tag.name
+    "#;
+    let tags: Vec<TagUsage> = sqlx::query_as(query).fetch_all(pool).await?;
+    if print_rows(&tags)? {
+        return Ok(());
+    }
+    if tags.is_empty() {
+        print_notice("No tag yet.".yellow().bold());
+        return Ok(());
+    }
+
+    let table = Table::new(tags)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_BRIGHT_WHITE,
+        ]))
+        .with(Panel::header("Tags\n"))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .to_string();
+
+    print_table(table);
+
+    Ok(())
+}
+
+/// the totals of every tag in the date range, transfers are left out.
+async fn tag_summary_rows(
+    range: &DateRange,
+    pool: &SqlitePool,
+) -> Result<Vec<TagSummary>, Box<dyn Error>> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        r#"
+        SELECT
+            tag.name,
+            COUNT(*) AS records,
+            SUM(CASE WHEN amount_record.in_or_out THEN amount_record.amount ELSE 0 END) AS income,
+            SUM(CASE WHEN amount_record.in_or_out THEN 0 ELSE amount_record.amount END) AS outcome
+        FROM
+            tag
+            JOIN record_tag ON record_tag.tag_id = tag.id
+            JOIN amount_record ON amount_record.id = record_tag.record_id
+        WHERE
+            amount_record.transfer_id IS NULL
+            AND amount_record.deleted_at IS NULL
+        "#,
+    );
+    push_date_range(&mut query, "amount_record.occurred_at", range);
+    query.push(" GROUP BY tag.id ORDER BY tag.name");
+
+    let mut summary: Vec<TagSummary> = query.build_query_as().fetch_all(pool).await?;
+    for x in summary.iter_mut() {
+        x.net = x.income - x.outcome;
+    }
+    Ok(summary)
+}
+
+/// print the totals of every tag in the date range.
+async fn tag_summary(args: &TagSummaryArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let range = args.range.range()?;
+    let summary = tag_summary_rows(&range, pool).await?;
+    if print_rows(&summary)? {
+        return Ok(());
+    }
+    if summary.is_empty() {
+        print_notice("No tagged record in the range.".yellow().bold());
+        return Ok(());
+    }
+
+    let header = format!("Tag Summary {}\n", range);
+    let table = Table::new(summary)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_GREEN,
+            Color::FG_BRIGHT_RED,
+            Color::FG_BRIGHT_BLUE,
+        ]))
+        .with(Panel::header(header))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .to_string();
+
+    print_table(table);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_customs::{AmountArgs, DateRangeArgs, OutputFormat};
+    use crate::database::test_database;
+    use crate::output::write_rows;
+    use crate::process::insert_in_or_out_come;
+
+    async fn summary_of_two_records() -> Vec<TagSummary> {
+        let database = test_database("tag-summary-output").await;
+        for (amount, in_or_out) in [("30", false), ("100", true)] {
+            let args = AmountArgs {
+                amount: amount.parse().unwrap(),
+                add_msg: None,
+                date: None,
+                account: None,
+                category: None,
+                tags: vec!["trip".to_string()],
+            };
+            insert_in_or_out_come(&args, database.clone(), in_or_out)
+                .await
+                .unwrap();
+        }
+        let pool = SqlitePool::connect_with(SqliteConnectOptions::new().filename(&database))
+            .await
+            .unwrap();
+        let range = DateRangeArgs::default().range().unwrap();
+        tag_summary_rows(&range, &pool).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn tag_summary_in_json() {
+        let rows = summary_of_two_records().await;
+        let mut out = Vec::new();
+        assert!(write_rows(OutputFormat::Json, &rows, &mut out).unwrap());
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["name"], "trip");
+        assert_eq!(json[0]["records"], 2);
+        assert_eq!(json[0]["net"], "70.00");
+    }
+
+    #[tokio::test]
+    async fn tag_summary_in_csv() {
+        let rows = summary_of_two_records().await;
+        let mut out = Vec::new();
+        assert!(write_rows(OutputFormat::Csv, &rows, &mut out).unwrap());
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "name,records,income,outcome,net\ntrip,2,100.00,30.00,70.00\n"
+        );
+    }
+}
@@ -0,0 +1,416 @@
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// The timezone used when nothing is configured, the old hard-coded UTC+8.
+pub const DEFAULT_TIMEZONE: &str = "Asia/Shanghai";
+
+static TIMEZONE: OnceLock<Tz> = OnceLock::new();
+
+/// Parse an IANA timezone name like `Europe/Berlin`.
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.trim().parse::<Tz>().map_err(|_| {
+        format!(
+            "unknown timezone `{}`, expected an IANA name like Europe/Berlin",
+            name
+        )
+    })
+}
+
+/// Set the timezone every date is interpreted and displayed in.
+/// Only the first call takes effect.
+pub fn set_timezone(tz: Tz) {
+    let _ = TIMEZONE.set(tz);
+}
+
+/// The timezone every date is interpreted and displayed in.
+pub fn local_tz() -> Tz {
+    *TIMEZONE.get_or_init(|| DEFAULT_TIMEZONE.parse().unwrap())
+}
+
+/// Convert a utc moment into the configured timezone.
+pub fn to_local(time: DateTime<Utc>) -> DateTime<Tz> {
+    time.with_timezone(&local_tz())
+}
+
+/// Convert a local wall-clock time into utc.
+///
+/// Ambiguous times (DST fall back) resolve to the earlier one,
+/// times skipped by DST spring forward move to the first valid moment after.
+pub fn local_to_utc(local: NaiveDateTime) -> DateTime<Utc> {
+    local_to_utc_in(local_tz(), local)
+}
+
+fn local_to_utc_in(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    let mut probe = local;
+    loop {
+        match tz.from_local_datetime(&probe) {
+            LocalResult::Single(x) => return x.to_utc(),
+            LocalResult::Ambiguous(earliest, _) => return earliest.to_utc(),
+            LocalResult::None => probe += Duration::minutes(15),
+        }
+    }
+}
+
+/// Parse the `--date` of a record into the moment it happened.
+///
+/// Accepts `today`, `yesterday`, `YYYY-MM-DD`, `YYYY-MM-DD HH:MM`
+/// and `YYYY-MM-DD HH:MM:SS`, all in local time.
+/// A bare date keeps the current time of day, so records of the same day
+/// stay in the order they were entered.
+pub fn parse_occurred_at(input: &str) -> Result<DateTime<Utc>, String> {
+    let now = to_local(Utc::now());
+    let input = input.trim();
+
+    let local = match input.to_lowercase().as_str() {
+        "today" | "now" => return Ok(now.to_utc()),
+        "yesterday" => NaiveDateTime::new(now.date_naive() - Duration::days(1), now.time()),
+        _ => {
+            if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+                NaiveDateTime::new(date, now.time())
+            } else if let Ok(datetime) = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S") {
+                datetime
+            } else if let Ok(datetime) = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M") {
+                datetime
+            } else {
+                return Err(format!(
+                    "invalid date `{}`, expected YYYY-MM-DD[ HH:MM[:SS]], today or yesterday",
+                    input
+                ));
+            }
+        }
+    };
+
+    Ok(local_to_utc(local))
+}
+
+/// The local date of today.
+pub fn local_today() -> NaiveDate {
+    to_local(Utc::now()).date_naive()
+}
+
+/// Parse a local date like `2025-03-02`, `today` or `yesterday`.
+pub fn parse_date(input: &str) -> Result<NaiveDate, String> {
+    let today = local_today();
+    match input.trim().to_lowercase().as_str() {
+        "today" => Ok(today),
+        "yesterday" => Ok(today - Duration::days(1)),
+        x => NaiveDate::parse_from_str(x, "%Y-%m-%d").map_err(|_| {
+            format!(
+                "invalid date `{}`, expected YYYY-MM-DD, today or yesterday",
+                input
+            )
+        }),
+    }
+}
+
+/// Parse a number of days like `30d`, `4w` or `30`.
+pub fn parse_days(input: &str) -> Result<i64, String> {
+    let input = input.trim().to_lowercase();
+    let (number, days) = match input.char_indices().last() {
+        Some((i, 'd')) => (&input[..i], 1),
+        Some((i, 'w')) => (&input[..i], 7),
+        _ => (input.as_str(), 1),
+    };
+    match number.parse::<i64>().ok().and_then(|x| x.checked_mul(days)) {
+        Some(x) if x >= 0 && Duration::try_days(x).is_some() => Ok(x),
+        _ => Err(format!(
+            "invalid days `{}`, expected a number with d or w, e.g. 30d",
+            input
+        )),
+    }
+}
+
+/// The utc moment of the local midnight of `current`.
+pub fn local_midnight(current: NaiveDate) -> DateTime<Utc> {
+    local_midnight_in(local_tz(), current)
+}
+
+fn local_midnight_in(tz: Tz, current: NaiveDate) -> DateTime<Utc> {
+    let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+    local_to_utc_in(tz, NaiveDateTime::new(current, midnight))
+}
+
+/// Parse a month like `2025-03` into its first day.
+pub fn parse_month(input: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("{}-01", input.trim()), "%Y-%m-%d")
+        .map_err(|_| format!("invalid month `{}`, expected YYYY-MM", input))
+}
+
+/// Parse an ISO week like `2025-W10` into its monday.
+pub fn parse_week(input: &str) -> Result<NaiveDate, String> {
+    let invalid = || format!("invalid week `{}`, expected YYYY-Www, e.g. 2025-W10", input);
+    let upper = input.trim().to_uppercase();
+    let (year, week) = upper.split_once('W').ok_or_else(invalid)?;
+    let year = year
+        .trim_end_matches('-')
+        .parse::<i32>()
+        .map_err(|_| invalid())?;
+    let week = week.parse::<u32>().map_err(|_| invalid())?;
+    NaiveDate::from_isoywd_opt(year, week, Weekday::Mon).ok_or_else(invalid)
+}
+
+/// Parse a quarter like `2025-Q1` into its first day.
+pub fn parse_quarter(input: &str) -> Result<NaiveDate, String> {
+    let invalid = || {
+        format!(
+            "invalid quarter `{}`, expected YYYY-Qn, e.g. 2025-Q1",
+            input
+        )
+    };
+    let upper = input.trim().to_uppercase();
+    let (year, quarter) = upper.split_once('Q').ok_or_else(invalid)?;
+    let year = year
+        .trim_end_matches('-')
+        .parse::<i32>()
+        .map_err(|_| invalid())?;
+    match quarter.parse::<u32>() {
+        Ok(x @ 1..=4) => NaiveDate::from_ymd_opt(year, x * 3 - 2, 1).ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+/// The utc range `[start, end)` of a local month like `2025-03`.
+pub fn month_range(month: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let first = parse_month(month)?;
+    let next = first
+        .checked_add_months(Months::new(1))
+        .ok_or(format!("date out of range, the month {} has no end", month))?;
+    Ok((local_midnight(first), local_midnight(next)))
+}
+
+/// An inclusive range of local dates, open on a side without a bound.
+/// The default range is all the time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DateRange {
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+}
+
+impl DateRange {
+    pub fn new(start: NaiveDate, end: NaiveDate) -> Self {
+        DateRange {
+            start: Some(start),
+            end: Some(end),
+        }
+    }
+
+    /// The `months` months from `first`, an error if they end after the last date.
+    fn months_from(first: NaiveDate, months: u32) -> Result<Self, String> {
+        let end = first
+            .checked_add_months(Months::new(months))
+            .and_then(|x| x.pred_opt())
+            .ok_or(format!(
+                "date out of range, the period from {} has no end",
+                first
+            ))?;
+        Ok(DateRange::new(first, end))
+    }
+
+    /// The month `day` is in.
+    pub fn month(day: NaiveDate) -> Result<Self, String> {
+        DateRange::months_from(day.with_day(1).unwrap(), 1)
+    }
+
+    /// The quarter `day` is in.
+    pub fn quarter(day: NaiveDate) -> Result<Self, String> {
+        let first = NaiveDate::from_ymd_opt(day.year(), day.month0() / 3 * 3 + 1, 1).unwrap();
+        DateRange::months_from(first, 3)
+    }
+
+    /// The year `day` is in.
+    pub fn year(day: NaiveDate) -> Result<Self, String> {
+        DateRange::months_from(NaiveDate::from_ymd_opt(day.year(), 1, 1).unwrap(), 12)
+    }
+
+    /// The week from monday to sunday `day` is in.
+    pub fn week(day: NaiveDate) -> Result<Self, String> {
+        let monday = day
+            .checked_sub_signed(Duration::days(day.weekday().num_days_from_monday() as i64))
+            .ok_or(format!(
+                "date out of range, the week of {} has no start",
+                day
+            ))?;
+        let sunday = monday
+            .checked_add_signed(Duration::days(6))
+            .ok_or(format!("date out of range, the week of {} has no end", day))?;
+        Ok(DateRange::new(monday, sunday))
+    }
+
+    /// The last `days` days up to today.
+    pub fn last_days(days: i64) -> Result<Self, String> {
+        let today = local_today();
+        let start = Duration::try_days(days - 1)
+            .and_then(|x| today.checked_sub_signed(x))
+            .ok_or(format!("{} days go before the first date", days))?;
+        Ok(DateRange::new(start, today))
+    }
+
+    /// Whether the range is all the time.
+    pub fn is_all(&self) -> bool {
+        self.start.is_none() && self.end.is_none()
+    }
+
+    /// The utc range `[start, end)` of the moments in the range.
+    pub fn utc_bounds(&self) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+        self.utc_bounds_in(local_tz())
+    }
+
+    fn utc_bounds_in(&self, tz: Tz) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+        (
+            self.start.map(|x| local_midnight_in(tz, x)),
+            // the range is open after the last date.
+            self.end
+                .and_then(|x| x.succ_opt())
+                .map(|x| local_midnight_in(tz, x)),
+        )
+    }
+}
+
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = |x: Option<NaiveDate>, open: &str| {
+            x.map(|x| x.format("%Y-%m-%d").to_string())
+                .unwrap_or(open.to_string())
+        };
+        write!(
+            f,
+            "{} ~ {}",
+            text(self.start, "beginning"),
+            text(self.end, "now")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(input: &str) -> NaiveDate {
+        parse_date(input).unwrap()
+    }
+
+    fn utc(input: &str) -> DateTime<Utc> {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn month_boundaries() {
+        let range = DateRange::month(date("2024-02-15")).unwrap();
+        assert_eq!(
+            range,
+            DateRange::new(date("2024-02-01"), date("2024-02-29"))
+        );
+        let range = DateRange::month(date("2025-12-31")).unwrap();
+        assert_eq!(
+            range,
+            DateRange::new(date("2025-12-01"), date("2025-12-31"))
+        );
+    }
+
+    #[test]
+    fn quarter_and_year_boundaries() {
+        let range = DateRange::quarter(parse_quarter("2025-Q2").unwrap()).unwrap();
+        assert_eq!(
+            range,
+            DateRange::new(date("2025-04-01"), date("2025-06-30"))
+        );
+        let range = DateRange::quarter(date("2025-12-31")).unwrap();
+        assert_eq!(
+            range,
+            DateRange::new(date("2025-10-01"), date("2025-12-31"))
+        );
+        let range = DateRange::year(date("2025-07-04")).unwrap();
+        assert_eq!(
+            range,
+            DateRange::new(date("2025-01-01"), date("2025-12-31"))
+        );
+        assert!(parse_quarter("2025-Q5").is_err());
+    }
+
+    #[test]
+    fn week_boundaries() {
+        // the first iso week of 2025 starts in 2024.
+        let range = DateRange::week(parse_week("2025-W01").unwrap()).unwrap();
+        assert_eq!(
+            range,
+            DateRange::new(date("2024-12-30"), date("2025-01-05"))
+        );
+        let range = DateRange::week(date("2025-03-09")).unwrap();
+        assert_eq!(
+            range,
+            DateRange::new(date("2025-03-03"), date("2025-03-09"))
+        );
+        assert!(parse_week("2025-W54").is_err());
+    }
+
+    #[test]
+    fn periods_at_the_last_date() {
+        let last = NaiveDate::MAX;
+        let ymd = |month, day| NaiveDate::from_ymd_opt(262142, month, day).unwrap();
+        assert_eq!(
+            DateRange::month(ymd(11, 15)).unwrap(),
+            DateRange::new(ymd(11, 1), ymd(11, 30))
+        );
+        assert!(DateRange::month(last).is_err());
+        assert!(DateRange::quarter(last).is_err());
+        assert!(DateRange::year(last).is_err());
+        assert!(DateRange::week(last).is_err());
+        assert!(DateRange::week(NaiveDate::MIN).is_err());
+        assert!(month_range("262142-12").is_err());
+        // the range is open after the last date.
+        let range = DateRange::new(last, last);
+        assert_eq!(range.utc_bounds().1, None);
+    }
+
+    #[test]
+    fn last_days_ends_today() {
+        let today = local_today();
+        let range = DateRange::last_days(1).unwrap();
+        assert_eq!(range, DateRange::new(today, today));
+        let range = DateRange::last_days(90).unwrap();
+        assert_eq!(range.start, Some(today - Duration::days(89)));
+        assert!(DateRange::last_days(i64::MAX / 86400).is_err());
+    }
+
+    #[test]
+    fn parse_days_in_days_and_weeks() {
+        assert_eq!(parse_days("30d"), Ok(30));
+        assert_eq!(parse_days("4W"), Ok(28));
+        assert_eq!(parse_days("7"), Ok(7));
+        assert!(parse_days("-1d").is_err());
+        assert!(parse_days("9999999999999999w").is_err());
+    }
+
+    #[test]
+    fn utc_bounds_follow_dst() {
+        let berlin = parse_timezone("Europe/Berlin").unwrap();
+        // march starts in winter time and ends in summer time.
+        let range = DateRange::month(date("2025-03-10")).unwrap();
+        assert_eq!(
+            range.utc_bounds_in(berlin),
+            (
+                Some(utc("2025-02-28T23:00:00Z")),
+                Some(utc("2025-03-31T22:00:00Z"))
+            )
+        );
+        // the day clocks go back has 25 hours.
+        let day = DateRange::new(date("2025-10-26"), date("2025-10-26"));
+        let (start, end) = day.utc_bounds_in(berlin);
+        assert_eq!(end.unwrap() - start.unwrap(), Duration::hours(25));
+    }
+
+    #[test]
+    fn midnight_skipped_by_dst_moves_forward() {
+        // in 2018 the clocks of Sao Paulo went from 00:00 to 01:00 on november 4.
+        let sao_paulo = parse_timezone("America/Sao_Paulo").unwrap();
+        assert_eq!(
+            local_midnight_in(sao_paulo, date("2018-11-04")),
+            utc("2018-11-04T03:00:00Z")
+        );
+    }
+}
@@ -0,0 +1,368 @@
+use crate::cli_customs::{TrashArgs, TrashCommands, TrashPurgeArgs, TrashRestoreArgs};
+use crate::journal::{journal, record_images};
+use crate::ledger::{append_chain, chain_image};
+use crate::money::Money;
+use crate::output::{confirm, print_notice, print_rows, print_table};
+use crate::process::{AMOUNT_SELECT, Amount, select_records_in};
+use crate::time_utils::to_local;
+use chrono::{Duration, Utc};
+use colored::*;
+use serde::Serialize;
+use sqlx::{QueryBuilder, Sqlite, SqlitePool, sqlite::SqliteConnectOptions};
+use std::error::Error;
+use std::path::PathBuf;
+use tabled::{
+    Table, Tabled,
+    settings::{
+        Alignment, Color, Highlight, Modify, Panel, Width,
+        object::{Rows, Segment},
+        style::{Border, BorderColor, Style},
+        themes::Colorization,
+    },
+};
+
+/// a row of the `trash list` output.
+#[derive(Clone, Debug, Tabled, Serialize)]
+struct TrashRow {
+    id: i32,
+    amount: Money,
+    in_or_out: bool,
+    append_msg: String,
+    account: String,
+    category: String,
+    tags: String,
+    occurred_at: String,
+    deleted_at: String,
+}
+
+/// dispatch the `trash` subcommands.
+pub async fn trash_command(args: &TrashArgs, database_path: PathBuf) -> Result<(), Box<dyn Error>> {
+    let options = SqliteConnectOptions::new().filename(&database_path);
+    let pool = SqlitePool::connect_with(options).await?;
+
+    match &args.command {
+        TrashCommands::List => list_trash(&pool).await,
+        TrashCommands::Restore(arg) => restore_records(arg, &pool).await,
+        TrashCommands::Purge(arg) => purge_records(arg, &pool).await,
+    }
+}
+
+async fn list_trash(pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let rows = trash_rows(pool).await?;
+    if print_rows(&rows)? {
+        return Ok(());
+    }
+    if rows.is_empty() {
+        print_notice("The trash is empty.".yellow().bold());
+        return Ok(());
+    }
+
+    let table = Table::new(rows)
+        .with(Style::blank())
+        .with(Colorization::columns([
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_BLUE,
+            Color::FG_BRIGHT_RED,
+            Color::FG_BRIGHT_CYAN,
+            Color::FG_BRIGHT_WHITE,
+            Color::FG_BRIGHT_YELLOW,
+            Color::FG_BRIGHT_GREEN,
+            Color::FG_MAGENTA,
+            Color::FG_RED,
+        ]))
+        .with(Panel::header("Trash\n"))
+        .modify(Rows::first(), Color::FG_BRIGHT_YELLOW)
+        .modify(Rows::first(), Alignment::center())
+        .with(Modify::new(Segment::all()).with(Width::wrap(40)))
+        .with(Highlight::new(Rows::single(2)).color(BorderColor::default().top(Color::FG_YELLOW)))
+        .with(Highlight::new(Rows::single(2)).border(Border::new().top('━')))
+        .to_string();
+
+    print_table(table);
+
+    Ok(())
+}
+
+/// the records in the trash, the earliest deleted first.
+async fn trash_rows(pool: &SqlitePool) -> Result<Vec<TrashRow>, Box<dyn Error>> {
+    let query = format!(
+        "{} WHERE amount_record.deleted_at IS NOT NULL ORDER BY amount_record.deleted_at, amount_record.id",
+        AMOUNT_SELECT
+    );
+    let records: Vec<Amount> = sqlx::query_as(&query).fetch_all(pool).await?;
+    let local = |time| to_local(time).format("%Y-%m-%d %H:%M:%S").to_string();
+    Ok(records
+        .iter()
+        .map(|x| TrashRow {
+            id: x.id,
+            amount: x.amount,
+            in_or_out: x.in_or_out,
+            append_msg: x.append_msg.clone(),
+            account: x.account.clone().unwrap_or_default(),
+            category: x.category.clone().unwrap_or_default(),
+            tags: x.tags.clone().unwrap_or_default(),
+            occurred_at: local(x.occurred_at),
+            deleted_at: x.deleted_at.map(local).unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// bring the records back, along with the other side of every transfer among them.
+async fn restore_records(args: &TrashRestoreArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT id, transfer_id FROM amount_record WHERE deleted_at IS NOT NULL AND (",
+    );
+    let mut separated = query.separated(" OR ");
+    for x in &args.ids {
+        separated
+            .push("id BETWEEN ")
+            .push_bind_unseparated(x.start)
+            .push_unseparated(" AND ")
+            .push_bind_unseparated(x.end);
+    }
+    query.push(")");
+    let selected: Vec<(i64, Option<i64>)> = query.build_query_as().fetch_all(pool).await?;
+    if selected.is_empty() {
+        return Err("no record in the trash matches the ids".into());
+    }
+
+    let mut ids: Vec<i64> = selected.iter().map(|x| x.0).collect();
+    let transfer_ids: Vec<i64> = selected.iter().filter_map(|x| x.1).collect();
+    if !transfer_ids.is_empty() {
+        ids.extend(
+            select_records_in(pool, "transfer_id", &transfer_ids)
+                .await?
+                .into_iter()
+                .filter(|x| x.deleted_at.is_some())
+                .map(|x| x.id as i64),
+        );
+    }
+    ids.sort_unstable();
+    ids.dedup();
+
+    let mut tx = pool.begin().await?;
+    let before = record_images(&mut tx, &ids).await?;
+    for id in &ids {
+        sqlx::query("UPDATE amount_record SET deleted_at = NULL WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    journal(&mut tx, "trash restore", before).await?;
+    tx.commit().await?;
+
+    print_notice(format!(
+        "{}{}",
+        "Restored records: ".green().bold(),
+        ids.iter()
+            .map(|x| format!("#{}", x))
+            .collect::<Vec<String>>()
+            .join(" ")
+            .purple()
+            .bold()
+    ));
+    Ok(())
+}
+
+/// remove the records in the trash permanently, they can not be undone.
+/// their changes are discarded from undo and redo, an operation is discarded
+/// once none of its records is left. only the hash chain, if it is enabled,
+/// keeps what they were.
+async fn purge_records(args: &TrashPurgeArgs, pool: &SqlitePool) -> Result<(), Box<dyn Error>> {
+    let days = args.older_than.unwrap_or(0);
+    let cutoff = Duration::try_days(days)
+        .and_then(|x| Utc::now().checked_sub_signed(x))
+        .ok_or(format!("{} days go before the first date", days))?;
+    let ids: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM amount_record WHERE deleted_at IS NOT NULL AND deleted_at <= ? ORDER BY id",
+    )
+    .bind(cutoff.format("%Y-%m-%d %H:%M:%S").to_string())
+    .fetch_all(pool)
+    .await?;
+    if ids.is_empty() {
+        print_notice("No record to purge.".yellow().bold());
+        return Ok(());
+    }
+
+    if !args.yes
+        && !confirm(format!(
+            "{}{}{}",
+            "Purge ".red().bold(),
+            format!("{} records", ids.len()).purple().bold(),
+            " permanently. Input Yes(YES/yes/Y/y) to confirm, other to give up."
+                .blue()
+                .bold()
+        ))?
+    {
+        print_notice("Give up".red().bold());
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut changes = Vec::new();
+    for (id, image) in record_images(&mut tx, &ids).await? {
+        changes.push((id, chain_image(image.as_ref())?, None));
+    }
+    for id in &ids {
+        sqlx::query("DELETE FROM record_tag WHERE record_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM amount_record WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    let mut query =
+        QueryBuilder::<Sqlite>::new("UPDATE operation_row SET discarded = 1 WHERE record_id IN (");
+    let mut separated = query.separated(", ");
+    for id in &ids {
+        separated.push_bind(id);
+    }
+    query.push(")");
+    query.build().execute(&mut *tx).await?;
+    sqlx::query(
+        r#"
+        UPDATE operation SET discarded = 1
+        WHERE
+            id IN (SELECT operation_id FROM operation_row WHERE discarded = 1)
+            AND NOT EXISTS (
+                SELECT 1 FROM operation_row
+                WHERE operation_row.operation_id = operation.id AND operation_row.discarded = 0
+            )
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM transfer WHERE id NOT IN (SELECT transfer_id FROM amount_record WHERE transfer_id IS NOT NULL)")
+        .execute(&mut *tx)
+        .await?;
+    append_chain(&mut tx, "trash purge", &changes).await?;
+    tx.commit().await?;
+
+    print_notice(format!(
+        "{}{}",
+        "Purged records: ".green().bold(),
+        ids.len().to_string().purple().bold()
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli_customs::{AmountArgs, Cli, Commands, DeleteRecordArgs, IdRange, UndoArgs};
+    use crate::database::test_database;
+    use crate::journal::undo_command;
+    use crate::process::{delete_record, insert_in_or_out_come, patch_record};
+    use clap::Parser;
+
+    #[tokio::test]
+    async fn undo_skips_the_operations_of_purged_records() {
+        let database = test_database("purge-undo").await;
+        for msg in ["kept", "purged"] {
+            let args = AmountArgs {
+                amount: "5".parse().unwrap(),
+                add_msg: Some(msg.to_string()),
+                date: None,
+                account: None,
+                category: None,
+                tags: Vec::new(),
+            };
+            insert_in_or_out_come(&args, database.clone(), true)
+                .await
+                .unwrap();
+        }
+        let args = DeleteRecordArgs {
+            ids: vec![IdRange { start: 2, end: 2 }],
+            select: Default::default(),
+            yes: true,
+            dry_run: false,
+        };
+        delete_record(&args, database.clone()).await.unwrap();
+        let args = TrashArgs {
+            command: TrashCommands::Purge(TrashPurgeArgs {
+                older_than: None,
+                yes: true,
+            }),
+        };
+        trash_command(&args, database.clone()).await.unwrap();
+
+        // the delete and the insert of #2 are skipped, the insert of #1 is undone.
+        undo_command(&UndoArgs { count: 1 }, database.clone())
+            .await
+            .unwrap();
+        let pool = SqlitePool::connect_with(SqliteConnectOptions::new().filename(&database))
+            .await
+            .unwrap();
+        let left: i64 = sqlx::query_scalar("SELECT count(*) FROM amount_record")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(left, 0);
+        let undone: Vec<i64> = sqlx::query_scalar("SELECT id FROM operation WHERE undone = 1")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(undone, vec![1]);
+    }
+
+    #[tokio::test]
+    async fn purge_keeps_the_undo_of_the_other_records() {
+        let database = test_database("purge-partial-undo").await;
+        for msg in ["kept", "purged"] {
+            let args = AmountArgs {
+                amount: "5".parse().unwrap(),
+                add_msg: Some(msg.to_string()),
+                date: None,
+                account: None,
+                category: None,
+                tags: Vec::new(),
+            };
+            insert_in_or_out_come(&args, database.clone(), true)
+                .await
+                .unwrap();
+        }
+        let Commands::PatchRecord(args) =
+            Cli::try_parse_from(["rustance", "patch-record", "1-2", "-a", "patched", "--yes"])
+                .unwrap()
+                .command
+        else {
+            unreachable!()
+        };
+        patch_record(&args, database.clone()).await.unwrap();
+        let args = DeleteRecordArgs {
+            ids: vec![IdRange { start: 2, end: 2 }],
+            select: Default::default(),
+            yes: true,
+            dry_run: false,
+        };
+        delete_record(&args, database.clone()).await.unwrap();
+        let args = TrashArgs {
+            command: TrashCommands::Purge(TrashPurgeArgs {
+                older_than: None,
+                yes: true,
+            }),
+        };
+        trash_command(&args, database.clone()).await.unwrap();
+
+        // the delete of #2 is skipped, the patch is undone for #1 only.
+        undo_command(&UndoArgs { count: 1 }, database.clone())
+            .await
+            .unwrap();
+        let pool = SqlitePool::connect_with(SqliteConnectOptions::new().filename(&database))
+            .await
+            .unwrap();
+        let msgs: Vec<(i64, String)> =
+            sqlx::query_as("SELECT id, append_msg FROM amount_record ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(msgs, vec![(1, "kept".to_string())]);
+        let undone: Vec<i64> = sqlx::query_scalar("SELECT id FROM operation WHERE undone = 1")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(undone, vec![3]);
+    }
+}